@@ -1,24 +1,36 @@
-use std::time::Instant;
-
-use anyhow::{bail, Result};
+use std::time::{Duration, Instant};
 
 use crate::{
     boardstate::Player,
+    error::{QuoridorError, Result},
     locations::{Coordinate, Location, PawnLocation, WallLocation, WallOrientation},
 };
 
 const ALPHABET: [char; 9] = ['A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I'];
 
+/// An action in the history of a game. The time spent is what was charged to the clock of the
+/// player, which is not always the difference between the times of two actions: a received action
+/// is charged up to the moment it was received, while the turn of the opponent only starts when
+/// it is executed. Use `get_time_spent` for the thinking time of a move.
 pub struct ExecutedAction {
     time: Instant,
     action: Action,
+    player: Player,
+    time_spent: Duration,
 }
 
 impl ExecutedAction {
-    pub fn new(time: Instant, action: Action) -> Self {
-        ExecutedAction { time, action }
+    pub fn new(time: Instant, action: Action, player: Player, time_spent: Duration) -> Self {
+        ExecutedAction {
+            time,
+            action,
+            player,
+            time_spent,
+        }
     }
 
+    /// The moment the action was charged to the clock, the moment it was received for received
+    /// actions.
     pub fn get_time(&self) -> Instant {
         self.time
     }
@@ -26,6 +38,15 @@ impl ExecutedAction {
     pub fn get_action(&self) -> Action {
         self.action
     }
+
+    pub fn get_player(&self) -> Player {
+        self.player
+    }
+
+    /// The time the player spent thinking on the action, charged to the clock of the player.
+    pub fn get_time_spent(&self) -> Duration {
+        self.time_spent
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Copy)]
@@ -45,12 +66,13 @@ impl Action {
     }
 
     pub fn from_notation(notation: &str) -> Result<Self> {
-        match notation.len() {
+        let length = notation.chars().count();
+        match length {
             2 => Ok(Action::Pawn(pawn_location_from_notation(notation)?)),
             3 => Ok(Action::Wall(wall_location_from_notation(notation)?)),
-            _ => bail!(format!(
-                "An action notation should have 2 or 3 characters, got {}",
-                notation.len()
+            _ => Err(invalid_notation(
+                notation,
+                format!("An action notation should have 2 or 3 characters, got {length}"),
             )),
         }
     }
@@ -63,8 +85,8 @@ impl Action {
                 Some(wall_location.get_orientation()),
             ),
             Self::Resigned(player) => match player {
-                Player::White => String::from("1-0"),
-                Player::Black => String::from("0-1"),
+                Player::White => String::from("0-1"),
+                Player::Black => String::from("1-0"),
             },
         }
     }
@@ -85,26 +107,33 @@ fn wall_location_from_notation(notation: &str) -> Result<WallLocation> {
             coordinate,
             WallOrientation::Horizontal,
         )?),
-        _ => bail!(String::from(
-            "The last character of the notation needs to be either a v or an h",
+        _ => Err(invalid_notation(
+            notation,
+            String::from("The last character of the notation needs to be either a v or an h"),
         )),
     }
 }
 
+fn invalid_notation(notation: &str, reason: String) -> QuoridorError {
+    QuoridorError::InvalidNotation {
+        notation: String::from(notation),
+        reason,
+    }
+}
+
 fn number_to_alphabet(number: u8) -> char {
     ALPHABET[usize::from(number)]
 }
 
 fn notation_to_coordinate(notation: &str) -> Result<Coordinate> {
-    let uppercase_first_char = notation
-        .to_ascii_uppercase()
-        .chars()
-        .next()
-        .expect("Verified that notation contains 2 chars in Action::from_notation");
-    let second_char = notation
-        .chars()
-        .nth(1)
-        .expect("Verified that notation contains 2 chars in Action::from_notation");
+    let mut chars = notation.chars();
+    let (Some(first_char), Some(second_char)) = (chars.next(), chars.next()) else {
+        return Err(invalid_notation(
+            notation,
+            String::from("A notation needs at least 2 characters"),
+        ));
+    };
+    let uppercase_first_char = first_char.to_ascii_uppercase();
 
     if let Ok(x) = ALPHABET.binary_search(&uppercase_first_char) {
         if let Some(y) = second_char.to_digit(10) {
@@ -114,13 +143,22 @@ fn notation_to_coordinate(notation: &str) -> Result<Coordinate> {
                     y: (y - 1).try_into().unwrap(),
                 });
             }
-            bail!(String::from("The second character can not be 0"));
+            return Err(invalid_notation(
+                notation,
+                String::from("The second character can not be 0"),
+            ));
         }
-        bail!(format!(
-            "The second character needs to be a number between 1 and 9, but got {second_char}"
+        return Err(invalid_notation(
+            notation,
+            format!(
+                "The second character needs to be a number between 1 and 9, but got {second_char}"
+            ),
         ));
     }
-    bail!(format!("The first character of a notation needs to be a letter between A and I, but got {uppercase_first_char}"))
+    Err(invalid_notation(
+        notation,
+        format!("The first character of a notation needs to be a letter between A and I, but got {uppercase_first_char}"),
+    ))
 }
 
 fn location_to_notation(coordinate: Coordinate, orientation: Option<WallOrientation>) -> String {
@@ -147,16 +185,19 @@ pub struct PossibleActions {
 impl PossibleActions {
     pub fn build(
         pawn_locations: Vec<PawnLocation>,
-        _wall_actions: Vec<WallLocation>,
+        wall_locations: Vec<WallLocation>,
     ) -> PossibleActions {
-        let pawn_actions = pawn_locations
+        let actions = pawn_locations
             .iter()
             .map(|location| Action::from_pawn_location(*location))
+            .chain(
+                wall_locations
+                    .iter()
+                    .map(|location| Action::from_wall_location(*location)),
+            )
             .collect();
 
-        PossibleActions {
-            actions: pawn_actions,
-        }
+        PossibleActions { actions }
     }
 
     pub fn get_actions(&self) -> &Vec<Action> {
@@ -220,4 +261,21 @@ mod tests {
             Action::from_notation(input).unwrap();
         }
     }
+
+    #[test]
+    fn invalid_notation_is_an_error() {
+        let inputs = [
+            "", "a", "x1v", "B0h", "c1x", "B1vx", "x1", "A12", "B0", "c0x", "é", "€", "Aé", "é1",
+            "A1é", "é1h",
+        ];
+        for input in inputs {
+            assert!(
+                matches!(
+                    Action::from_notation(input),
+                    Err(QuoridorError::InvalidNotation { .. })
+                ),
+                "{input}"
+            );
+        }
+    }
 }
@@ -1,13 +1,17 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
 
-use anyhow::{bail, ensure, Result};
 use bitmaps::Bitmap;
+use rand::seq::SliceRandom;
+use rand::Rng;
 
 use crate::actions::{Action, PossibleActions};
+use crate::error::{QuoridorError, Result};
 use crate::gamestate::{GameStatus, VictoryReason};
 use crate::locations::{
     Coordinate, Direction, Location, PawnLocation, WallLocation, WallOrientation,
 };
+use crate::zobrist;
 
 const DIRECTIONS: [Direction; 4] = [
     Direction::North,
@@ -16,7 +20,14 @@ const DIRECTIONS: [Direction; 4] = [
     Direction::West,
 ];
 
-#[derive(Clone, Hash, Debug)]
+/// The number of places a wall can be put, 64 centers in both orientations.
+const WALL_CANDIDATES: usize = 128;
+
+/// How many random candidates `random_action` tries before it falls back to picking from all the
+/// legal actions.
+const RANDOM_ACTION_ATTEMPTS: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 /// The boardstate is responsible for keeping track of all the pawns and walls placed on the board.
 ///
 /// It is also the place that holds the basic game rules related logic, since it is the place that
@@ -33,11 +44,12 @@ pub struct Boardstate {
     black_available_walls: u8,
     wall_placed: Bitmap<71>,
     wall_orientation: Bitmap<71>,
+    zobrist_key: u64,
 }
 
 impl Default for Boardstate {
     fn default() -> Boardstate {
-        Boardstate {
+        let mut boardstate = Boardstate {
             active_player: Player::White,
             white_position: PawnLocation::build(4)
                 .expect("White player starting location on square 5 should be a valid location."),
@@ -47,7 +59,18 @@ impl Default for Boardstate {
             black_available_walls: 10,
             wall_placed: Bitmap::<71>::new(),
             wall_orientation: Bitmap::<71>::new(),
-        }
+            zobrist_key: 0,
+        };
+        boardstate.zobrist_key = boardstate.calculate_zobrist_key();
+        boardstate
+    }
+}
+
+/// Only the Zobrist key is hashed, since it already identifies the position and is a lot cheaper
+/// to hash than all the fields.
+impl Hash for Boardstate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.zobrist_key.hash(state);
     }
 }
 
@@ -66,15 +89,136 @@ impl Boardstate {
         for wall_location in walls {
             boardstate.insert_wall_at_location(wall_location)?;
         }
-        boardstate.white_position = white;
-        boardstate.black_position = black;
+        boardstate.set_pawn_position(Player::White, white);
+        boardstate.set_pawn_position(Player::Black, black);
         if let Some(player) = active_player {
-            boardstate.active_player = player;
+            boardstate.set_active_player(player);
+        }
+
+        Ok(boardstate)
+    }
+
+    /// Creates a boardstate from a single line position notation, made up off six fields that
+    /// are separated by spaces:
+    ///
+    /// 1. The square of the white pawn, for example `E1`
+    /// 2. The square of the black pawn, for example `E9`
+    /// 3. The placed walls separated by commas, for example `C3h,E6v`, or `-` without walls
+    /// 4. The number of walls white has left
+    /// 5. The number of walls black has left
+    /// 6. The active player, `w` for white or `b` for black
+    ///
+    /// The starting position is written as `E1 E9 - 10 10 w`.
+    pub fn from_position_notation(notation: &str) -> Result<Boardstate> {
+        let fields: Vec<&str> = notation.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(QuoridorError::InvalidNotation {
+                notation: String::from(notation),
+                reason: format!(
+                    "A position notation should have 6 fields, but got {}",
+                    fields.len()
+                ),
+            });
+        }
+
+        let white = pawn_location_from_position_field(fields[0])?;
+        let black = pawn_location_from_position_field(fields[1])?;
+        if white == black {
+            return Err(QuoridorError::InvalidPosition {
+                reason: format!("Both pawns can't be on square {}", fields[0]),
+            });
+        }
+
+        let mut boardstate = Boardstate::new();
+        boardstate.set_pawn_position(Player::White, white);
+        boardstate.set_pawn_position(Player::Black, black);
+
+        let mut walls_placed = 0u8;
+        if fields[2] != "-" {
+            for wall_notation in fields[2].split(',') {
+                let location = wall_location_from_position_field(wall_notation)?;
+                if boardstate.wall_crosses_existing_wall(location) {
+                    return Err(QuoridorError::WallCrosses {
+                        square: location.get_square(),
+                    });
+                }
+                if boardstate.wall_overlaps_existing_wall(location) {
+                    return Err(QuoridorError::WallOverlaps {
+                        square: location.get_square(),
+                    });
+                }
+                boardstate.set_wall(location);
+                walls_placed += 1;
+            }
+        }
+        if !boardstate.players_can_reach_opposite_side() {
+            return Err(QuoridorError::InvalidPosition {
+                reason: String::from("The walls block a player from reaching the other side"),
+            });
+        }
+
+        let white_available_walls = available_walls_from_notation(fields[3])?;
+        let black_available_walls = available_walls_from_notation(fields[4])?;
+        if walls_placed + white_available_walls + black_available_walls > 20 {
+            return Err(QuoridorError::InvalidPosition {
+                reason: format!(
+                    "There are only 20 walls, but {walls_placed} are placed and {} are left",
+                    white_available_walls + black_available_walls
+                ),
+            });
+        }
+        boardstate.set_available_walls(Player::White, white_available_walls);
+        boardstate.set_available_walls(Player::Black, black_available_walls);
+
+        match fields[5] {
+            "w" | "W" => boardstate.set_active_player(Player::White),
+            "b" | "B" => boardstate.set_active_player(Player::Black),
+            other => {
+                return Err(QuoridorError::InvalidNotation {
+                    notation: String::from(other),
+                    reason: String::from("The active player should be either w or b"),
+                })
+            }
         }
 
         Ok(boardstate)
     }
 
+    /// Writes the boardstate in the position notation that is read by `from_position_notation`.
+    pub fn get_position_notation(&self) -> String {
+        let walls: Vec<String> = self
+            .get_wall_positions()
+            .iter()
+            .enumerate()
+            .filter_map(|(square, wall)| {
+                wall.map(|orientation| {
+                    Action::Wall(
+                        WallLocation::build(square as u8, orientation)
+                            .expect("Placed walls are always on a valid wall location"),
+                    )
+                    .get_notation()
+                })
+            })
+            .collect();
+
+        format!(
+            "{} {} {} {} {} {}",
+            Action::Pawn(self.white_position).get_notation(),
+            Action::Pawn(self.black_position).get_notation(),
+            if walls.is_empty() {
+                String::from("-")
+            } else {
+                walls.join(",")
+            },
+            self.white_available_walls,
+            self.black_available_walls,
+            match self.active_player {
+                Player::White => 'w',
+                Player::Black => 'b',
+            }
+        )
+    }
+
     pub fn get_active_player(&self) -> Player {
         self.active_player
     }
@@ -95,6 +239,13 @@ impl Boardstate {
         self.black_available_walls
     }
 
+    /// The 64 bit Zobrist hash of the position, it covers the position of both pawns, the placed
+    /// walls, the available walls per player and the active player. The key is updated
+    /// incrementally when actions are applied, so it is cheap to use for transposition tables.
+    pub fn get_zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+
     pub fn get_wall_positions(&self) -> [Option<WallOrientation>; 71] {
         let mut wall_arrray: [Option<WallOrientation>; 71] = [const { None }; 71];
         for index in self.wall_placed.into_iter() {
@@ -168,6 +319,73 @@ impl Boardstate {
         wall_option_1 | wall_option_2
     }
 
+    pub fn get_position_pawn(&self, player: Player) -> PawnLocation {
+        match player {
+            Player::White => self.white_position,
+            Player::Black => self.black_position,
+        }
+    }
+
+    /// Calculates for every square on the board the number of steps it takes the player to reach
+    /// its goal row, using a breadth first search that starts from the goal row and works its way
+    /// back over the board. Squares that are cut off from the goal row by walls are None.
+    ///
+    /// The pawns are not taken into account, so jumps are not counted as shortcuts.
+    pub fn get_distance_to_goal_map(&self, player: Player) -> [Option<u8>; 81] {
+        let mut distances: [Option<u8>; 81] = [None; 81];
+        let mut to_explore: VecDeque<PawnLocation> = VecDeque::with_capacity(81);
+
+        let goal = player.get_goal_row();
+        for x in 0..=8u8 {
+            let location = PawnLocation::from_coordinate(Coordinate { x, y: goal })
+                .expect("The x range is made to be small enough");
+            distances[usize::from(location.get_square())] = Some(0);
+            to_explore.push_back(location);
+        }
+
+        while let Some(current) = to_explore.pop_front() {
+            let distance = distances[usize::from(current.get_square())]
+                .expect("Only squares with a distance are added to to_explore");
+            for next in self.get_neighbours_from_location(current) {
+                let next_distance = &mut distances[usize::from(next.get_square())];
+                if next_distance.is_none() {
+                    *next_distance = Some(distance + 1);
+                    to_explore.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// The number of steps the pawn of the player needs to reach its goal row, None when the pawn
+    /// is blocked off from the goal row.
+    pub fn get_distance_to_goal(&self, player: Player) -> Option<u8> {
+        self.get_distance_to_goal_map(player)
+            [usize::from(self.get_position_pawn(player).get_square())]
+    }
+
+    /// Finds one of the shortest paths from the pawn of the player to its goal row. When multiple
+    /// paths have the same length the first found one is returned, where the directions are tried
+    /// in the order north, east, south, west.
+    pub fn get_shortest_path(&self, player: Player) -> Option<ShortestPath> {
+        let distances = self.get_distance_to_goal_map(player);
+        let mut current = self.get_position_pawn(player);
+        let distance = distances[usize::from(current.get_square())]?;
+
+        let mut path: Vec<PawnLocation> = Vec::with_capacity(usize::from(distance));
+        for remaining in (0..distance).rev() {
+            current = self
+                .get_neighbours_from_location(current)
+                .into_iter()
+                .find(|next| distances[usize::from(next.get_square())] == Some(remaining))
+                .expect("A square with a distance always has a neighbour one step closer");
+            path.push(current);
+        }
+
+        Some(ShortestPath { distance, path })
+    }
+
     /// Method to get all the legal moves for the currently active player in the
     /// current boadstate.
     ///
@@ -177,7 +395,10 @@ impl Boardstate {
     /// be available (not blocked by existing wall) and check if it doesn't block all paths to the
     /// opposite side for either player.
     pub fn get_legal_actions(&self) -> PossibleActions {
-        PossibleActions::build(self.get_possible_pawn_moves_for_active_player(), Vec::new())
+        PossibleActions::build(
+            self.get_possible_pawn_moves_for_active_player(),
+            self.get_possible_wall_moves_for_active_player(),
+        )
     }
 
     /// The play action takes an action as input and attempts to play that move on the current
@@ -190,27 +411,176 @@ impl Boardstate {
             Action::Pawn(pawn_location) => self.move_pawn_to_location(pawn_location),
             Action::Wall(wall_location) => self.insert_wall_at_location(wall_location),
             Action::Resigned(player) => Ok(GameStatus::Finished {
-                won_by: player,
+                won_by: player.get_opponent(),
                 reason: VictoryReason::Resigned,
             }),
         }
     }
 
-    fn move_pawn_to_location(&mut self, location: PawnLocation) -> Result<GameStatus> {
-        ensure!(
-            self.get_possible_pawn_moves_for_active_player()
-                .contains(&location),
-            format!("The move to square {} is not legal.", location.get_square())
-        );
-        match self.active_player {
-            Player::White => {
-                self.white_position = location;
+    /// Applies the action the same way as `apply_action`, but also returns an `UndoAction` that
+    /// can be passed to `unapply_action` to restore the boardstate to how it was before the
+    /// action. This allows searching through moves without cloning the board for every node.
+    pub fn apply_action_reversible(&mut self, action: Action) -> Result<(GameStatus, UndoAction)> {
+        let undo = UndoAction {
+            action,
+            active_player: self.active_player,
+            previous_position: self.get_position_active_pawn(),
+        };
+        let status = self.apply_action(action)?;
+
+        Ok((status, undo))
+    }
+
+    /// Restores the boardstate to the state before the action of the `UndoAction` was applied.
+    ///
+    /// Undo actions have to be unapplied in the reverse order from how they were applied, else
+    /// the restored boardstate will not be a state that was ever on the board.
+    pub fn unapply_action(&mut self, undo: UndoAction) {
+        match undo.action {
+            Action::Pawn(_) => self.set_pawn_position(undo.active_player, undo.previous_position),
+            Action::Wall(wall_location) => {
+                self.remove_wall(wall_location);
+                self.set_available_walls(
+                    undo.active_player,
+                    self.get_available_walls(undo.active_player) + 1,
+                );
             }
-            Player::Black => {
-                self.black_position = location;
+            Action::Resigned(_) => (),
+        }
+        self.set_active_player(undo.active_player);
+    }
+
+    /// Picks a random legal action for the active player, every legal action has the same chance
+    /// of being picked. Returns None when the active player has no legal actions.
+    ///
+    /// Instead of generating all the legal walls, a random pawn move or wall candidate is drawn
+    /// and only that candidate is checked, until a legal one comes up. Most wall candidates are
+    /// legal, so this is a lot cheaper than building the `PossibleActions`.
+    pub fn random_action<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Action> {
+        let pawn_moves = self.get_possible_pawn_moves_for_active_player();
+        let wall_candidates = if self.player_has_walls_available() {
+            WALL_CANDIDATES
+        } else {
+            0
+        };
+        let candidates = pawn_moves.len() + wall_candidates;
+        if candidates == 0 {
+            return None;
+        }
+
+        for _ in 0..RANDOM_ACTION_ATTEMPTS {
+            let index = rng.gen_range(0..candidates);
+            if let Some(location) = pawn_moves.get(index) {
+                return Some(Action::Pawn(*location));
+            }
+            let location = wall_candidate(index - pawn_moves.len());
+            if self.wall_is_legal(location) {
+                return Some(Action::Wall(location));
             }
         }
 
+        // Nearly all the wall candidates are illegal, so pick from the complete list instead.
+        self.get_legal_actions().get_actions().choose(rng).copied()
+    }
+
+    /// Plays actions chosen by the policy until the game is finished and returns how it finished.
+    /// The boardstate is left in the final position, clone it first to keep the current position.
+    ///
+    /// All the randomness comes from the rng, so a playout with a seeded rng is reproducible.
+    pub fn playout<R: Rng + ?Sized>(&mut self, rng: &mut R, policy: PlayoutPolicy) -> GameStatus {
+        self.playout_with_limit(rng, policy, usize::MAX)
+    }
+
+    /// The same as `playout`, but stops after `max_plies` actions. When the game isn't finished by
+    /// then, or the active player has no legal actions, `GameStatus::InProgress` is returned.
+    pub fn playout_with_limit<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        policy: PlayoutPolicy,
+        max_plies: usize,
+    ) -> GameStatus {
+        for _ in 0..max_plies {
+            let Some(action) = self.playout_action(rng, policy) else {
+                break;
+            };
+            let status = self.apply_legal_action(action);
+            if status != GameStatus::InProgress {
+                return status;
+            }
+        }
+
+        GameStatus::InProgress
+    }
+
+    fn playout_action<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        policy: PlayoutPolicy,
+    ) -> Option<Action> {
+        if let PlayoutPolicy::ShortestPathBiased { probability } = policy {
+            if rng.gen_bool(probability.clamp(0.0, 1.0)) {
+                if let Some(location) = self.get_pawn_move_closest_to_goal() {
+                    return Some(Action::Pawn(location));
+                }
+            }
+        }
+
+        self.random_action(rng)
+    }
+
+    /// The pawn move of the active player that ends closest to the goal row, jumps over the other
+    /// pawn included.
+    fn get_pawn_move_closest_to_goal(&self) -> Option<PawnLocation> {
+        let distances = self.get_distance_to_goal_map(self.active_player);
+        self.get_possible_pawn_moves_for_active_player()
+            .into_iter()
+            .filter_map(|location| {
+                distances[usize::from(location.get_square())].map(|distance| (distance, location))
+            })
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, location)| location)
+    }
+
+    /// Applies an action that is known to be legal without checking it again, this saves the path
+    /// finding for walls during playouts.
+    fn apply_legal_action(&mut self, action: Action) -> GameStatus {
+        match action {
+            Action::Pawn(location) => {
+                self.set_pawn_position(self.active_player, location);
+                if self.is_won() {
+                    return GameStatus::Finished {
+                        won_by: self.active_player,
+                        reason: VictoryReason::ReachedOppositeSide,
+                    };
+                }
+            }
+            Action::Wall(location) => {
+                self.set_wall(location);
+                self.decrease_available_walls();
+            }
+            Action::Resigned(player) => {
+                return GameStatus::Finished {
+                    won_by: player.get_opponent(),
+                    reason: VictoryReason::Resigned,
+                }
+            }
+        }
+
+        self.swap_active_player();
+        GameStatus::InProgress
+    }
+
+    fn move_pawn_to_location(&mut self, location: PawnLocation) -> Result<GameStatus> {
+        if !self
+            .get_possible_pawn_moves_for_active_player()
+            .contains(&location)
+        {
+            return Err(QuoridorError::IllegalPawnMove {
+                square: location.get_square(),
+            });
+        }
+        self.set_pawn_position(self.active_player, location);
+
         if self.is_won() {
             return Ok(GameStatus::Finished {
                 won_by: self.get_active_player(),
@@ -223,12 +593,11 @@ impl Boardstate {
     }
 
     fn get_possible_pawn_moves_for_active_player(&self) -> Vec<PawnLocation> {
-        let current_location = match self.active_player {
-            Player::White => self.white_position,
-            Player::Black => self.black_position,
-        };
+        self.get_possible_pawn_moves_from_location(self.get_position_active_pawn())
+    }
 
-        self.get_possible_pawn_moves_from_location(current_location)
+    fn get_position_active_pawn(&self) -> PawnLocation {
+        self.get_position_pawn(self.active_player)
     }
 
     fn get_possible_pawn_moves_from_location(&self, location: PawnLocation) -> Vec<PawnLocation> {
@@ -321,10 +690,22 @@ impl Boardstate {
     }
 
     fn swap_active_player(&mut self) {
-        self.active_player = match self.active_player {
-            Player::White => Player::Black,
-            Player::Black => Player::White,
+        self.set_active_player(self.active_player.get_opponent());
+    }
+
+    fn set_active_player(&mut self, player: Player) {
+        self.zobrist_key ^= zobrist::active_player_key(self.active_player);
+        self.active_player = player;
+        self.zobrist_key ^= zobrist::active_player_key(self.active_player);
+    }
+
+    fn set_pawn_position(&mut self, player: Player, location: PawnLocation) {
+        self.zobrist_key ^= zobrist::pawn_key(player, self.get_position_pawn(player).get_square());
+        match player {
+            Player::White => self.white_position = location,
+            Player::Black => self.black_position = location,
         }
+        self.zobrist_key ^= zobrist::pawn_key(player, location.get_square());
     }
 
     /// Attempts to insert a wall for the currently active player.
@@ -334,58 +715,109 @@ impl Boardstate {
     ///
     /// At a successfull insert the active player is swapped.
     fn insert_wall_at_location(&mut self, location: WallLocation) -> Result<GameStatus> {
-        ensure!(
-            self.player_has_walls_available(),
-            "No more walls left make a pawn move instead"
-        );
+        if !self.player_has_walls_available() {
+            return Err(QuoridorError::NoWallsLeft);
+        }
 
-        let square = usize::from(location.get_square());
-        let coordinate = location.get_coordinate();
+        let square = location.get_square();
 
-        ensure!(
-            self.get_wall_at_coordinate(coordinate).is_none(),
-            format!("Can't insert wall, location {} already occupied", square)
-        );
+        if self.wall_crosses_existing_wall(location) {
+            return Err(QuoridorError::WallCrosses { square });
+        }
+        if self.wall_overlaps_existing_wall(location) {
+            return Err(QuoridorError::WallOverlaps { square });
+        }
+        if !self.wall_keeps_paths_open(location) {
+            return Err(QuoridorError::PathBlocked { square });
+        }
 
-        match location.get_orientation() {
-            WallOrientation::Vertical => {
-                ensure!(
-                    !self.vertical_wall_at_coordinate(coordinate),
-                    format!(
-                        "Can't insert wall, location {} overlaps with existing wall",
-                        square
-                    )
-                );
+        self.set_wall(location);
+        self.decrease_available_walls();
+        self.swap_active_player();
+        Ok(GameStatus::InProgress)
+    }
+
+    /// Collects every wall location the active player could legally place a wall on.
+    ///
+    /// All 64 wall centers are tried in both orientations, a wall is legal when it doesn't cross
+    /// or overlap an already placed wall and both pawns can still reach the opposite side.
+    fn get_possible_wall_moves_for_active_player(&self) -> Vec<WallLocation> {
+        let mut possible_wall_moves: Vec<WallLocation> = Vec::with_capacity(128);
+        if !self.player_has_walls_available() {
+            return possible_wall_moves;
+        }
 
-                self.wall_orientation.set(square, true);
+        for y in 0..8u8 {
+            for x in 0..8u8 {
+                for orientation in [WallOrientation::Horizontal, WallOrientation::Vertical] {
+                    let location = WallLocation::from_coordinate(Coordinate { x, y }, orientation)
+                        .expect("The x and y range are made to be small enough");
+                    if self.wall_is_legal(location) {
+                        possible_wall_moves.push(location);
+                    }
+                }
             }
+        }
 
-            WallOrientation::Horizontal => {
-                ensure!(
-                    !self.horizontal_wall_at_coordinate(coordinate),
-                    format!(
-                        "Can't insert wall, location {} overlaps with existing wall",
-                        square
-                    )
-                );
+        possible_wall_moves
+    }
 
-                self.wall_orientation.set(square, false);
+    /// Checks everything about placing the wall except the number of walls the player has left.
+    fn wall_is_legal(&self, location: WallLocation) -> bool {
+        !self.wall_crosses_existing_wall(location)
+            && !self.wall_overlaps_existing_wall(location)
+            && self.wall_keeps_paths_open(location)
+    }
+
+    /// A wall crosses another wall when both share the same center, regardless of orientation.
+    fn wall_crosses_existing_wall(&self, location: WallLocation) -> bool {
+        self.get_wall_at_coordinate(location.get_coordinate())
+            .is_some()
+    }
+
+    /// A wall overlaps another wall with the same orientation when one of its two segments is
+    /// already covered, this can be a wall centered one step before or one step after it.
+    fn wall_overlaps_existing_wall(&self, location: WallLocation) -> bool {
+        let coordinate = location.get_coordinate();
+        match location.get_orientation() {
+            WallOrientation::Horizontal => {
+                self.horizontal_wall_at_coordinate(coordinate)
+                    || coordinate
+                        .from_calculation(1, 0)
+                        .is_some_and(|next| self.horizontal_wall_at_coordinate(next))
+            }
+            WallOrientation::Vertical => {
+                self.vertical_wall_at_coordinate(coordinate)
+                    || coordinate
+                        .from_calculation(0, 1)
+                        .is_some_and(|next| self.vertical_wall_at_coordinate(next))
             }
         }
+    }
 
-        self.wall_placed.set(square, true);
+    /// Places the wall on a copy of the board to check that both players can still reach the
+    /// opposite side.
+    fn wall_keeps_paths_open(&self, location: WallLocation) -> bool {
+        let mut boardstate = self.clone();
+        boardstate.set_wall(location);
+        boardstate.players_can_reach_opposite_side()
+    }
 
-        if !self.players_can_reach_opposite_side() {
-            self.wall_placed.set(square, false);
-            bail!(format!(
-                "Can't insert wall at location {}, a player is blocked from reaching the other side",
-                square
-        ))
-        }
+    fn set_wall(&mut self, location: WallLocation) {
+        let square = usize::from(location.get_square());
+        self.wall_placed.set(square, true);
+        self.wall_orientation.set(
+            square,
+            location.get_orientation() == WallOrientation::Vertical,
+        );
+        self.zobrist_key ^= zobrist::wall_key(location.get_orientation(), location.get_square());
+    }
 
-        self.decrease_available_walls();
-        self.swap_active_player();
-        Ok(GameStatus::InProgress)
+    fn remove_wall(&mut self, location: WallLocation) {
+        let square = usize::from(location.get_square());
+        self.wall_placed.set(square, false);
+        self.wall_orientation.set(square, false);
+        self.zobrist_key ^= zobrist::wall_key(location.get_orientation(), location.get_square());
     }
 
     fn player_has_walls_available(&self) -> bool {
@@ -410,51 +842,209 @@ impl Boardstate {
     }
 
     fn path_is_available_for_player(&self, player: Player) -> bool {
-        let (start, goal) = match player {
-            Player::White => (self.get_position_white_pawn(), 8),
-            Player::Black => (self.get_position_black_pawn(), 0),
-        };
+        let start = self.get_position_pawn(player);
+        let goal = player.get_goal_row();
 
         let mut to_explore: VecDeque<PawnLocation> = VecDeque::with_capacity(40);
-        let mut seen: HashSet<PawnLocation> = HashSet::new();
+        let mut seen = [false; 81];
 
         to_explore.push_back(start);
+        seen[usize::from(start.get_square())] = true;
 
-        let mut current;
-        let mut opposite_side_reached = false;
-        while !to_explore.is_empty() && !opposite_side_reached {
-            current = to_explore
-                .pop_front()
-                .expect("While loop should quit when to_explore is empty");
-            for next in self.get_possible_pawn_moves_from_location(current) {
-                if !seen.contains(&next) {
+        while let Some(current) = to_explore.pop_front() {
+            if current.get_coordinate().y == goal {
+                return true;
+            }
+            for next in self.get_neighbours_from_location(current) {
+                if !seen[usize::from(next.get_square())] {
                     to_explore.push_back(next);
-                    seen.insert(next);
-                    opposite_side_reached = current.get_coordinate().y == goal
+                    seen[usize::from(next.get_square())] = true;
                 }
             }
         }
 
-        opposite_side_reached
+        false
+    }
+
+    /// The squares that can be reached in a single step from the location when only walls are
+    /// taken into account. The position of the pawns is ignored, since pawns can move out of the
+    /// way they don't count towards blocking a path.
+    fn get_neighbours_from_location(&self, location: PawnLocation) -> Vec<PawnLocation> {
+        DIRECTIONS
+            .iter()
+            .filter(|direction| !self.is_blocked_in_direction(location, **direction))
+            .map(|direction| {
+                location.from_direction(*direction).expect(
+                    "Going off the board should be handled by the is_blocked_in_direction method",
+                )
+            })
+            .collect()
     }
 
     fn decrease_available_walls(&mut self) {
-        match self.active_player {
-            Player::White => self.white_available_walls -= 1,
-            Player::Black => self.black_available_walls -= 1,
+        self.set_available_walls(
+            self.active_player,
+            self.get_available_walls(self.active_player) - 1,
+        );
+    }
+
+    fn get_available_walls(&self, player: Player) -> u8 {
+        match player {
+            Player::White => self.white_available_walls,
+            Player::Black => self.black_available_walls,
+        }
+    }
+
+    fn set_available_walls(&mut self, player: Player, available_walls: u8) {
+        self.zobrist_key ^= zobrist::available_walls_key(player, self.get_available_walls(player));
+        match player {
+            Player::White => self.white_available_walls = available_walls,
+            Player::Black => self.black_available_walls = available_walls,
+        }
+        self.zobrist_key ^= zobrist::available_walls_key(player, available_walls);
+    }
+
+    /// Calculates the Zobrist key from scratch, only needed when a boardstate is created, after
+    /// that the key is kept up to date with every change to the board.
+    fn calculate_zobrist_key(&self) -> u64 {
+        let mut key = zobrist::active_player_key(self.active_player)
+            ^ zobrist::pawn_key(Player::White, self.white_position.get_square())
+            ^ zobrist::pawn_key(Player::Black, self.black_position.get_square())
+            ^ zobrist::available_walls_key(Player::White, self.white_available_walls)
+            ^ zobrist::available_walls_key(Player::Black, self.black_available_walls);
+        for (square, wall) in self.get_wall_positions().iter().enumerate() {
+            if let Some(orientation) = wall {
+                key ^= zobrist::wall_key(*orientation, square as u8);
+            }
         }
+        key
     }
 }
 
-#[derive(Copy, Clone, Hash, Debug, PartialEq)]
+fn pawn_location_from_position_field(notation: &str) -> Result<PawnLocation> {
+    match Action::from_notation(notation)? {
+        Action::Pawn(location) => Ok(location),
+        _ => Err(QuoridorError::InvalidNotation {
+            notation: String::from(notation),
+            reason: String::from("Expected the square of a pawn"),
+        }),
+    }
+}
+
+fn wall_location_from_position_field(notation: &str) -> Result<WallLocation> {
+    match Action::from_notation(notation)? {
+        Action::Wall(location) => Ok(location),
+        _ => Err(QuoridorError::InvalidNotation {
+            notation: String::from(notation),
+            reason: String::from("Expected the location of a wall"),
+        }),
+    }
+}
+
+/// The wall location of a candidate index from `0..WALL_CANDIDATES`, the two orientations of a
+/// center are next to each other.
+fn wall_candidate(index: usize) -> WallLocation {
+    let center =
+        u8::try_from(index / 2).expect("The index is smaller than the number of candidates");
+    let orientation = if index.is_multiple_of(2) {
+        WallOrientation::Horizontal
+    } else {
+        WallOrientation::Vertical
+    };
+    WallLocation::from_coordinate(
+        Coordinate {
+            x: center % 8,
+            y: center / 8,
+        },
+        orientation,
+    )
+    .expect("The center is on the 8 by 8 grid of wall centers")
+}
+
+fn available_walls_from_notation(notation: &str) -> Result<u8> {
+    match notation.parse::<u8>() {
+        Ok(available_walls) if available_walls <= 10 => Ok(available_walls),
+        _ => Err(QuoridorError::InvalidNotation {
+            notation: String::from(notation),
+            reason: String::from("The number of walls left should be between 0 and 10"),
+        }),
+    }
+}
+
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq)]
 /// An enum with the two player options
 pub enum Player {
     White,
     Black,
 }
 
+impl Player {
+    pub fn get_opponent(&self) -> Player {
+        match self {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+        }
+    }
+
+    /// The row the pawn of the player needs to reach to win the game.
+    pub fn get_goal_row(&self) -> u8 {
+        match self {
+            Player::White => 8,
+            Player::Black => 0,
+        }
+    }
+}
+
+/// How the actions of a playout are chosen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PlayoutPolicy {
+    /// Every legal action has the same chance of being played.
+    Random,
+    /// With the given probability the pawn takes the step that brings it closest to the goal,
+    /// otherwise a random legal action is played. Fully random playouts spend most of their
+    /// actions placing walls, which makes the outcome say little about the position.
+    ShortestPathBiased { probability: f64 },
+}
+
+/// A shortest route for a pawn to its goal row. The path contains every square the pawn steps
+/// on, excluding the square it currently occupies, so the last square is on the goal row.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShortestPath {
+    distance: u8,
+    path: Vec<PawnLocation>,
+}
+
+impl ShortestPath {
+    pub fn get_distance(&self) -> u8 {
+        self.distance
+    }
+
+    pub fn get_path(&self) -> &Vec<PawnLocation> {
+        &self.path
+    }
+}
+
+/// The information needed to take back an action applied with
+/// `Boardstate::apply_action_reversible`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UndoAction {
+    action: Action,
+    active_player: Player,
+    previous_position: PawnLocation,
+}
+
+impl UndoAction {
+    pub fn get_action(&self) -> Action {
+        self.action
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -776,4 +1366,488 @@ mod tests {
             .insert_wall_at_location(WallLocation::build(42, WallOrientation::Horizontal).unwrap())
             .unwrap();
     }
+
+    #[test]
+    #[should_panic]
+    fn insert_wall_failed_overlap_from_the_right() {
+        let mut boardstate = Boardstate::new();
+        boardstate
+            .insert_wall_at_location(WallLocation::build(42, WallOrientation::Horizontal).unwrap())
+            .unwrap();
+        boardstate
+            .insert_wall_at_location(WallLocation::build(41, WallOrientation::Horizontal).unwrap())
+            .unwrap();
+    }
+
+    #[test]
+    fn legal_actions_starting_position() {
+        let boardstate = Boardstate::new();
+        let actions = boardstate.get_legal_actions();
+        let wall_actions = actions
+            .get_actions()
+            .iter()
+            .filter(|action| matches!(action, Action::Wall(_)))
+            .count();
+        assert_eq!(actions.get_actions().len(), 131);
+        assert_eq!(wall_actions, 128);
+    }
+
+    #[test]
+    fn legal_wall_moves_exclude_crossing_and_overlapping_walls() {
+        let mut boardstate = Boardstate::new();
+        boardstate
+            .insert_wall_at_location(WallLocation::build(41, WallOrientation::Horizontal).unwrap())
+            .unwrap();
+        let possible_wall_moves = boardstate.get_possible_wall_moves_for_active_player();
+        assert_eq!(possible_wall_moves.len(), 124);
+        for (square, orientation) in [
+            (40, WallOrientation::Horizontal),
+            (41, WallOrientation::Horizontal),
+            (42, WallOrientation::Horizontal),
+            (41, WallOrientation::Vertical),
+        ] {
+            assert!(
+                !possible_wall_moves.contains(&WallLocation::build(square, orientation).unwrap())
+            );
+        }
+        assert!(possible_wall_moves
+            .contains(&WallLocation::build(40, WallOrientation::Vertical).unwrap()));
+    }
+
+    #[test]
+    fn legal_wall_moves_exclude_blocking_walls() {
+        let boardstate = Boardstate::start_from(
+            PawnLocation::build(4).unwrap(),
+            PawnLocation::build(76).unwrap(),
+            vec![
+                WallLocation::build(36, WallOrientation::Horizontal).unwrap(),
+                WallLocation::build(38, WallOrientation::Horizontal).unwrap(),
+                WallLocation::build(40, WallOrientation::Horizontal).unwrap(),
+                WallLocation::build(42, WallOrientation::Horizontal).unwrap(),
+                WallLocation::build(43, WallOrientation::Vertical).unwrap(),
+            ],
+            None,
+        )
+        .unwrap();
+        let possible_wall_moves = boardstate.get_possible_wall_moves_for_active_player();
+        assert!(!possible_wall_moves
+            .contains(&WallLocation::build(52, WallOrientation::Horizontal).unwrap()));
+        assert!(possible_wall_moves
+            .contains(&WallLocation::build(51, WallOrientation::Horizontal).unwrap()));
+    }
+
+    #[test]
+    fn legal_wall_moves_empty_without_walls_left() {
+        let mut boardstate = Boardstate::new();
+        boardstate.white_available_walls = 0;
+        assert!(boardstate
+            .get_possible_wall_moves_for_active_player()
+            .is_empty());
+        assert_eq!(boardstate.get_legal_actions().get_actions().len(), 3);
+    }
+
+    #[test]
+    fn illegal_actions_return_the_reason() {
+        let mut boardstate = Boardstate::new();
+        boardstate
+            .apply_action(Action::Wall(
+                WallLocation::build(41, WallOrientation::Horizontal).unwrap(),
+            ))
+            .unwrap();
+        for (square, orientation, error) in [
+            (
+                41,
+                WallOrientation::Vertical,
+                QuoridorError::WallCrosses { square: 41 },
+            ),
+            (
+                42,
+                WallOrientation::Horizontal,
+                QuoridorError::WallOverlaps { square: 42 },
+            ),
+        ] {
+            assert_eq!(
+                boardstate
+                    .apply_action(Action::Wall(
+                        WallLocation::build(square, orientation).unwrap()
+                    ))
+                    .unwrap_err(),
+                error
+            );
+        }
+        assert_eq!(
+            boardstate
+                .apply_action(Action::Pawn(PawnLocation::build(58).unwrap()))
+                .unwrap_err(),
+            QuoridorError::IllegalPawnMove { square: 58 }
+        );
+
+        boardstate.black_available_walls = 0;
+        assert_eq!(
+            boardstate
+                .apply_action(Action::Wall(
+                    WallLocation::build(0, WallOrientation::Horizontal).unwrap()
+                ))
+                .unwrap_err(),
+            QuoridorError::NoWallsLeft
+        );
+    }
+
+    #[test]
+    fn resigning_player_loses() {
+        let mut boardstate = Boardstate::new();
+        assert_eq!(
+            boardstate
+                .apply_action(Action::Resigned(Player::White))
+                .unwrap(),
+            GameStatus::Finished {
+                won_by: Player::Black,
+                reason: VictoryReason::Resigned
+            }
+        );
+    }
+
+    #[test]
+    fn unapply_pawn_move() {
+        let mut boardstate = Boardstate::new();
+        let (_, undo) = boardstate
+            .apply_action_reversible(Action::Pawn(PawnLocation::build(13).unwrap()))
+            .unwrap();
+        boardstate.unapply_action(undo);
+        assert_eq!(boardstate, Boardstate::new());
+    }
+
+    #[test]
+    fn unapply_wall_move() {
+        let mut boardstate = Boardstate::new();
+        let (_, undo) = boardstate
+            .apply_action_reversible(Action::Wall(
+                WallLocation::build(41, WallOrientation::Vertical).unwrap(),
+            ))
+            .unwrap();
+        assert_eq!(boardstate.get_available_walls_white_player(), 9);
+        boardstate.unapply_action(undo);
+        assert_eq!(boardstate, Boardstate::new());
+        assert_eq!(boardstate.get_wall_positions()[41], None);
+    }
+
+    #[test]
+    fn unapply_winning_move() {
+        let mut boardstate = Boardstate::start_from(
+            PawnLocation::build(67).unwrap(),
+            PawnLocation::build(14).unwrap(),
+            Vec::new(),
+            None,
+        )
+        .unwrap();
+        let before = boardstate.clone();
+        let (status, undo) = boardstate
+            .apply_action_reversible(Action::Pawn(PawnLocation::build(76).unwrap()))
+            .unwrap();
+        assert_ne!(status, GameStatus::InProgress);
+        boardstate.unapply_action(undo);
+        assert_eq!(boardstate, before);
+    }
+
+    #[test]
+    fn failed_reversible_action_leaves_board_untouched() {
+        let mut boardstate = Boardstate::new();
+        assert!(boardstate
+            .apply_action_reversible(Action::Pawn(PawnLocation::build(40).unwrap()))
+            .is_err());
+        assert_eq!(boardstate, Boardstate::new());
+    }
+
+    #[test]
+    fn apply_and_unapply_random_games() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut boardstate = Boardstate::new();
+            let mut history: Vec<(Boardstate, UndoAction)> = Vec::new();
+
+            for _ in 0..60 {
+                let actions = boardstate.get_legal_actions();
+                let action = *actions.get_actions().choose(&mut rng).unwrap();
+                let before = boardstate.clone();
+                let (status, undo) = boardstate.apply_action_reversible(action).unwrap();
+                history.push((before, undo));
+                if status != GameStatus::InProgress {
+                    break;
+                }
+            }
+
+            while let Some((before, undo)) = history.pop() {
+                boardstate.unapply_action(undo);
+                assert_eq!(boardstate, before);
+            }
+            assert_eq!(boardstate, Boardstate::new());
+        }
+    }
+
+    #[test]
+    fn distance_to_goal_map_empty_board() {
+        let boardstate = Boardstate::new();
+        let white_distances = boardstate.get_distance_to_goal_map(Player::White);
+        let black_distances = boardstate.get_distance_to_goal_map(Player::Black);
+        for square in 0..=80u8 {
+            let y = Coordinate::from_square(square).y;
+            assert_eq!(white_distances[usize::from(square)], Some(8 - y));
+            assert_eq!(black_distances[usize::from(square)], Some(y));
+        }
+    }
+
+    #[test]
+    fn shortest_path_empty_board() {
+        let boardstate = Boardstate::new();
+        let shortest_path = boardstate.get_shortest_path(Player::White).unwrap();
+        let expected: Vec<PawnLocation> = [13, 22, 31, 40, 49, 58, 67, 76]
+            .iter()
+            .map(|square| PawnLocation::build(*square).unwrap())
+            .collect();
+        assert_eq!(shortest_path.get_distance(), 8);
+        assert_eq!(shortest_path.get_path(), &expected);
+        assert_eq!(boardstate.get_distance_to_goal(Player::Black), Some(8));
+    }
+
+    #[test]
+    fn shortest_path_around_wall() {
+        let boardstate = Boardstate::start_from(
+            PawnLocation::build(4).unwrap(),
+            PawnLocation::build(76).unwrap(),
+            vec![
+                WallLocation::build(3, WallOrientation::Horizontal).unwrap(),
+                WallLocation::build(5, WallOrientation::Horizontal).unwrap(),
+            ],
+            None,
+        )
+        .unwrap();
+        let shortest_path = boardstate.get_shortest_path(Player::White).unwrap();
+        assert_eq!(shortest_path.get_distance(), 10);
+        assert_eq!(shortest_path.get_path().len(), 10);
+        assert_eq!(
+            shortest_path.get_path().last().unwrap().get_coordinate().y,
+            8
+        );
+        assert_eq!(boardstate.get_distance_to_goal(Player::Black), Some(10));
+    }
+
+    #[test]
+    fn shortest_path_ignores_other_pawn() {
+        let boardstate = Boardstate::start_from(
+            PawnLocation::build(40).unwrap(),
+            PawnLocation::build(49).unwrap(),
+            Vec::new(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(boardstate.get_distance_to_goal(Player::White), Some(4));
+        assert_eq!(boardstate.get_distance_to_goal(Player::Black), Some(5));
+    }
+
+    #[test]
+    fn zobrist_key_is_updated_incrementally() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut boardstate = Boardstate::new();
+        for _ in 0..40 {
+            let actions = boardstate.get_legal_actions();
+            let action = *actions.get_actions().choose(&mut rng).unwrap();
+            if boardstate.apply_action(action).unwrap() != GameStatus::InProgress {
+                break;
+            }
+            assert_eq!(
+                boardstate.get_zobrist_key(),
+                boardstate.calculate_zobrist_key()
+            );
+        }
+    }
+
+    #[test]
+    fn zobrist_key_transposed_move_orders() {
+        let first_order = ["E2", "E8", "C3h", "F6v", "D1v", "E7"];
+        let second_order = ["D1v", "F6v", "E2", "E8", "C3h", "E7"];
+
+        let mut first = Boardstate::new();
+        for notation in first_order {
+            first
+                .apply_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+        let mut second = Boardstate::new();
+        for notation in second_order {
+            second
+                .apply_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(first, second);
+        assert_eq!(first.get_zobrist_key(), second.get_zobrist_key());
+    }
+
+    #[test]
+    fn zobrist_key_depends_on_active_player_and_walls() {
+        let white_to_move = Boardstate::new();
+        let black_to_move = Boardstate::start_from(
+            PawnLocation::build(4).unwrap(),
+            PawnLocation::build(76).unwrap(),
+            Vec::new(),
+            Some(Player::Black),
+        )
+        .unwrap();
+        assert_ne!(
+            white_to_move.get_zobrist_key(),
+            black_to_move.get_zobrist_key()
+        );
+
+        let mut horizontal = Boardstate::new();
+        horizontal
+            .apply_action(Action::from_notation("D4h").unwrap())
+            .unwrap();
+        let mut vertical = Boardstate::new();
+        vertical
+            .apply_action(Action::from_notation("D4v").unwrap())
+            .unwrap();
+        assert_ne!(horizontal.get_zobrist_key(), vertical.get_zobrist_key());
+    }
+
+    #[test]
+    fn position_notation_starting_position() {
+        let boardstate = Boardstate::new();
+        assert_eq!(boardstate.get_position_notation(), "E1 E9 - 10 10 w");
+        assert_eq!(
+            Boardstate::from_position_notation("E1 E9 - 10 10 w").unwrap(),
+            boardstate
+        );
+    }
+
+    #[test]
+    fn position_notation_round_trip() {
+        let mut boardstate = Boardstate::new();
+        for notation in ["E2", "E8", "C3h", "F6v", "D1v"] {
+            boardstate
+                .apply_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+        let position_notation = boardstate.get_position_notation();
+        assert_eq!(position_notation, "E2 E8 D1v,C3h,F6v 8 9 b");
+        assert_eq!(
+            Boardstate::from_position_notation(&position_notation).unwrap(),
+            boardstate
+        );
+    }
+
+    #[test]
+    fn position_notation_failed() {
+        let inputs = [
+            "E1 E9 - 10 10",
+            "E1 E1 - 10 10 w",
+            "E1 E9 C3h,D3h 10 8 w",
+            "E1 E9 C3h,C3v 10 8 w",
+            "E1 E9 - 11 10 w",
+            "E1 E9 C3h 10 10 w",
+            "E1 E9 - 10 10 x",
+            "J1 E9 - 10 10 w",
+            "E1 E9 C3 10 9 w",
+            "E12 E9 - 10 10 w",
+        ];
+        for input in inputs {
+            assert!(
+                Boardstate::from_position_notation(input).is_err(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn random_actions_are_legal() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut boardstate = Boardstate::new();
+        for _ in 0..80 {
+            let action = boardstate.random_action(&mut rng).unwrap();
+            assert!(boardstate
+                .get_legal_actions()
+                .get_actions()
+                .contains(&action));
+            if boardstate.apply_action(action).unwrap() != GameStatus::InProgress {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn random_action_without_walls_left_moves_the_pawn() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let boardstate = Boardstate::from_position_notation("E1 E9 - 0 0 w").unwrap();
+        for _ in 0..20 {
+            assert!(matches!(
+                boardstate.random_action(&mut rng),
+                Some(Action::Pawn(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn wall_candidates_cover_every_wall_once() {
+        let mut candidates: Vec<WallLocation> = (0..WALL_CANDIDATES).map(wall_candidate).collect();
+        let mut possible_wall_moves = Boardstate::new().get_possible_wall_moves_for_active_player();
+        let key = |location: &WallLocation| {
+            (
+                location.get_square(),
+                location.get_orientation() == WallOrientation::Vertical,
+            )
+        };
+        candidates.sort_by_key(key);
+        possible_wall_moves.sort_by_key(key);
+        assert_eq!(candidates, possible_wall_moves);
+    }
+
+    #[test]
+    fn playouts_finish_and_are_reproducible() {
+        for policy in [
+            PlayoutPolicy::Random,
+            PlayoutPolicy::ShortestPathBiased { probability: 0.5 },
+        ] {
+            let mut first = Boardstate::new();
+            let status = first.playout(&mut StdRng::seed_from_u64(3), policy);
+            assert!(matches!(
+                status,
+                GameStatus::Finished {
+                    reason: VictoryReason::ReachedOppositeSide,
+                    ..
+                }
+            ));
+
+            let mut second = Boardstate::new();
+            assert_eq!(
+                second.playout(&mut StdRng::seed_from_u64(3), policy),
+                status
+            );
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn shortest_path_playout_walks_straight_to_the_goal() {
+        let mut boardstate = Boardstate::from_position_notation("A1 I9 - 10 10 w").unwrap();
+        let status = boardstate.playout(
+            &mut StdRng::seed_from_u64(0),
+            PlayoutPolicy::ShortestPathBiased { probability: 1.0 },
+        );
+        assert_eq!(
+            status,
+            GameStatus::Finished {
+                won_by: Player::White,
+                reason: VictoryReason::ReachedOppositeSide
+            }
+        );
+        assert_eq!(boardstate.get_position_white_pawn().get_square(), 72);
+    }
+
+    #[test]
+    fn playout_with_limit_stops_early() {
+        let mut boardstate = Boardstate::new();
+        let status =
+            boardstate.playout_with_limit(&mut StdRng::seed_from_u64(0), PlayoutPolicy::Random, 4);
+        assert_eq!(status, GameStatus::InProgress);
+        assert_eq!(boardstate.get_active_player(), Player::White);
+    }
 }
@@ -0,0 +1,104 @@
+use std::fmt;
+
+use crate::boardstate::Player;
+use crate::locations::Direction;
+
+pub type Result<T> = std::result::Result<T, QuoridorError>;
+
+/// Every reason an operation of the quoridor core can fail. The variants carry the details
+/// needed to explain to a player why a move is illegal, and can be matched on by engines without
+/// having to parse the error message.
+#[derive(Debug, PartialEq, Clone)]
+pub enum QuoridorError {
+    /// The square is not on the board, or not a square a wall can be centered on.
+    SquareOutOfRange { square: u8 },
+    /// The coordinate is not on the board, or not a coordinate a wall can be centered on.
+    CoordinateOutOfRange { x: u8, y: u8 },
+    /// Moving from the square in the direction leaves the board.
+    OffTheBoard { square: u8, direction: Direction },
+    /// The text could not be read as an action, position or game record.
+    InvalidNotation { notation: String, reason: String },
+    /// The position notation is readable, but describes a position that can't occur.
+    InvalidPosition { reason: String },
+    /// The game record is readable, but the tags don't agree with the moves.
+    InvalidRecord { reason: String },
+    /// A move in a game record is illegal, the ply counts from 1 for the first move of white.
+    IllegalRecordMove {
+        ply: usize,
+        notation: String,
+        reason: Box<QuoridorError>,
+    },
+    /// A wall is already centered on the square, crossing the new wall or on the same spot.
+    WallCrosses { square: u8 },
+    /// The new wall overlaps half of a wall with the same orientation.
+    WallOverlaps { square: u8 },
+    /// The new wall blocks a pawn from reaching the opposite side.
+    PathBlocked { square: u8 },
+    /// The active player has placed all of its walls.
+    NoWallsLeft,
+    /// The active pawn can't move to the square.
+    IllegalPawnMove { square: u8 },
+    /// No more actions can be executed once the game is finished.
+    GameFinished,
+    /// The player tried to move while it is the turn of the opponent.
+    NotYourTurn { player: Player },
+    /// The player tried to resign on behalf of the opponent.
+    ResignForOpponent { player: Player },
+    /// There are not enough moves played to take back.
+    NoMoveToTakeBack,
+}
+
+impl fmt::Display for QuoridorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QuoridorError::SquareOutOfRange { square } => {
+                write!(f, "The square {square} is out of range")
+            }
+            QuoridorError::CoordinateOutOfRange { x, y } => {
+                write!(f, "The coordinate x: {x} y: {y} is out of range")
+            }
+            QuoridorError::OffTheBoard { square, direction } => write!(
+                f,
+                "Can't move {:?} from square {}, it is off the board",
+                direction, square
+            ),
+            QuoridorError::InvalidNotation { notation, reason } => {
+                write!(f, "Invalid notation {notation}: {reason}")
+            }
+            QuoridorError::InvalidPosition { reason } => write!(f, "Invalid position: {reason}"),
+            QuoridorError::InvalidRecord { reason } => write!(f, "Invalid record: {reason}"),
+            QuoridorError::IllegalRecordMove {
+                ply,
+                notation,
+                reason,
+            } => write!(f, "Illegal move {notation} at ply {ply}: {reason}"),
+            QuoridorError::WallCrosses { square } => {
+                write!(f, "Can't insert wall, location {square} already occupied")
+            }
+            QuoridorError::WallOverlaps { square } => write!(
+                f,
+                "Can't insert wall, location {square} overlaps with existing wall"
+            ),
+            QuoridorError::PathBlocked { square } => write!(
+                f,
+                "Can't insert wall at location {square}, a player is blocked from reaching the other side"
+            ),
+            QuoridorError::NoWallsLeft => {
+                write!(f, "No more walls left make a pawn move instead")
+            }
+            QuoridorError::IllegalPawnMove { square } => {
+                write!(f, "The move to square {square} is not legal.")
+            }
+            QuoridorError::GameFinished => write!(f, "The game is already finished"),
+            QuoridorError::NotYourTurn { player } => {
+                write!(f, "It is not the turn of {:?}", player)
+            }
+            QuoridorError::ResignForOpponent { player } => {
+                write!(f, "{:?} can only resign for itself", player)
+            }
+            QuoridorError::NoMoveToTakeBack => write!(f, "There is no move to take back"),
+        }
+    }
+}
+
+impl std::error::Error for QuoridorError {}
@@ -1,30 +1,44 @@
 #![allow(dead_code)]
 
-use anyhow::{Ok, Result};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use crate::actions::{Action, ExecutedAction};
-use crate::boardstate::{Boardstate, Player};
+use crate::boardstate::{Boardstate, Player, UndoAction};
+use crate::error::{QuoridorError, Result};
 
 pub struct Gamestate {
     board_state: Boardstate,
+    clock: Arc<dyn Clock>,
     start_time: Instant,
+    turn_start_time: Instant,
     time_control: TimeControl,
     white_time_used: Duration,
     black_time_used: Duration,
     moves: Vec<ExecutedAction>,
+    history: Vec<PlySnapshot>,
     status: GameStatus,
 }
 
 impl Gamestate {
     pub fn new(time_control: TimeControl) -> Self {
+        Gamestate::with_clock(time_control, Arc::new(SystemClock))
+    }
+
+    /// Creates a gamestate that reads the time from the given clock, which makes it possible to
+    /// control the passing of time in tests or to replay a game with the original timing.
+    pub fn with_clock(time_control: TimeControl, clock: Arc<dyn Clock>) -> Self {
+        let start_time = clock.now();
         Gamestate {
             board_state: Boardstate::new(),
-            start_time: Instant::now(),
+            clock,
+            start_time,
+            turn_start_time: start_time,
             time_control,
             white_time_used: Duration::new(0, 0),
             black_time_used: Duration::new(0, 0),
             moves: Vec::new(),
+            history: Vec::new(),
             status: GameStatus::InProgress,
         }
     }
@@ -33,21 +47,349 @@ impl Gamestate {
         &self.board_state
     }
 
+    pub fn get_time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    pub fn get_moves(&self) -> &Vec<ExecutedAction> {
+        &self.moves
+    }
+
+    pub fn get_status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn get_start_time(&self) -> Instant {
+        self.start_time
+    }
+
+    /// The total time the player spent thinking on moves, including the time of the running
+    /// turn when it is the turn of the player.
+    pub fn get_time_used(&self, player: Player) -> Duration {
+        self.get_time_used_at(player, self.clock.now())
+    }
+
+    fn get_time_used_at(&self, player: Player, time: Instant) -> Duration {
+        let time_used = match player {
+            Player::White => self.white_time_used,
+            Player::Black => self.black_time_used,
+        };
+        if self.status == GameStatus::InProgress && self.board_state.get_active_player() == player {
+            return time_used + time.saturating_duration_since(self.turn_start_time);
+        }
+        time_used
+    }
+
+    /// The time the player has left on the clock. Games without a time limit have no remaining
+    /// time, so None is returned.
+    pub fn get_remaining_time(&self, player: Player) -> Option<Duration> {
+        self.get_remaining_time_at(player, self.clock.now())
+    }
+
+    fn get_remaining_time_at(&self, player: Player, time: Instant) -> Option<Duration> {
+        self.get_time_available(player).map(|time_available| {
+            time_available.saturating_sub(self.get_time_used_at(player, time))
+        })
+    }
+
+    /// Checks if the active player has run out of time, and if so ends the game. Useful to detect
+    /// a flag without waiting for the player to make a move.
+    pub fn check_flag(&mut self) -> GameStatus {
+        self.check_flag_at(self.clock.now())
+    }
+
+    fn check_flag_at(&mut self, time: Instant) -> GameStatus {
+        if self.status == GameStatus::InProgress {
+            let player = self.board_state.get_active_player();
+            if self.get_remaining_time_at(player, time) == Some(Duration::ZERO) {
+                self.flag(player);
+            }
+        }
+        self.status
+    }
+
+    /// Executes the action for the active player and charges the time since the previous move to
+    /// the clock of the player. When the player ran out of time before the action was received,
+    /// the action is not executed and the game is lost on time.
+    ///
+    /// A resignation can be executed by either player at any moment, the resigning player loses.
+    /// Once the game is finished no more actions can be executed.
     pub fn execute_action(&mut self, action: Action) -> Result<GameStatus> {
-        let time = Instant::now();
-        let status = self.board_state.apply_action(action)?;
+        self.execute_action_at(action, self.clock.now())
+    }
+
+    fn execute_action_at(&mut self, action: Action, time: Instant) -> Result<GameStatus> {
+        if self.status != GameStatus::InProgress {
+            return Err(QuoridorError::GameFinished);
+        }
 
-        self.moves.push(ExecutedAction::new(time, action));
+        if let Action::Resigned(player) = action {
+            let (status, undo) = self.board_state.apply_action_reversible(action)?;
+            self.history.push(self.create_snapshot(undo));
+            self.moves
+                .push(ExecutedAction::new(time, action, player, Duration::ZERO));
+            self.status = status;
+            return Ok(self.status);
+        }
+
+        let player = self.board_state.get_active_player();
+        if self.check_flag_at(time) != GameStatus::InProgress {
+            return Ok(self.status);
+        }
+
+        let (status, undo) = self.board_state.apply_action_reversible(action)?;
+        self.history.push(self.create_snapshot(undo));
+        let time_spent = time.saturating_duration_since(self.turn_start_time);
+        match player {
+            Player::White => self.white_time_used += time_spent,
+            Player::Black => self.black_time_used += time_spent,
+        }
+        // The turn of the opponent starts when the action is executed, the time between receiving
+        // and executing a received action is charged to neither player.
+        self.turn_start_time = self.clock.now();
+
+        self.moves
+            .push(ExecutedAction::new(time, action, player, time_spent));
         self.status = status;
 
         Ok(self.status)
     }
+
+    /// Executes the action on behalf of the player, which makes sure that a player only moves on
+    /// its own turn and only resigns for itself. Use this when the actions come from the players
+    /// themselves, like over a network connection.
+    pub fn execute_action_for(&mut self, player: Player, action: Action) -> Result<GameStatus> {
+        self.check_player(player, action)?;
+        self.execute_action(action)
+    }
+
+    /// Executes the action on behalf of the player like `execute_action_for`, but charges the
+    /// clock up to the moment the action was received instead of the moment it is executed. The
+    /// time it takes to get from receiving an action to executing it, like waiting for a lock, is
+    /// then not lost by the player. The time is limited to the current turn and can't be in the
+    /// future.
+    pub fn execute_received_action(
+        &mut self,
+        player: Player,
+        action: Action,
+        received_at: Instant,
+    ) -> Result<GameStatus> {
+        self.check_player(player, action)?;
+        let time = received_at.max(self.turn_start_time).min(self.clock.now());
+        self.execute_action_at(action, time)
+    }
+
+    fn check_player(&self, player: Player, action: Action) -> Result<()> {
+        match action {
+            Action::Resigned(resigning_player) => {
+                if resigning_player != player {
+                    return Err(QuoridorError::ResignForOpponent { player });
+                }
+            }
+            Action::Pawn(_) | Action::Wall(_) => {
+                if self.status == GameStatus::InProgress
+                    && self.board_state.get_active_player() != player
+                {
+                    return Err(QuoridorError::NotYourTurn { player });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The player resigns the game, which is won by the opponent.
+    pub fn resign(&mut self, player: Player) -> Result<GameStatus> {
+        self.execute_action(Action::Resigned(player))
+    }
+
+    /// Ends the game because the player left the game, like when a player disconnected and did
+    /// not come back. The game is won by the opponent.
+    pub fn abandon(&mut self, player: Player) -> Result<GameStatus> {
+        if self.status != GameStatus::InProgress {
+            return Err(QuoridorError::GameFinished);
+        }
+        self.end_game(player.get_opponent(), VictoryReason::Abandoned);
+        Ok(self.status)
+    }
+
+    /// Takes back the last ply, restoring the board, the clocks and the status to how they were
+    /// before the ply was played. The player that made the ply is to move again and its clock
+    /// starts running from the moment of the takeback.
+    ///
+    /// Taking back a ply of a finished game is only possible when `allow_finished` is set, since
+    /// it reopens a game that already has a result.
+    pub fn take_back_ply(&mut self, allow_finished: bool) -> Result<()> {
+        if self.moves.is_empty() {
+            return Err(QuoridorError::NoMoveToTakeBack);
+        }
+        if !allow_finished && self.status != GameStatus::InProgress {
+            return Err(QuoridorError::GameFinished);
+        }
+
+        let snapshot = self
+            .history
+            .pop()
+            .expect("Every executed action has a snapshot in the history");
+        self.moves.pop();
+        self.board_state.unapply_action(snapshot.undo);
+        self.white_time_used = snapshot.white_time_used;
+        self.black_time_used = snapshot.black_time_used;
+        self.turn_start_time = self.clock.now();
+        self.status = GameStatus::InProgress;
+
+        Ok(())
+    }
+
+    /// Takes back the last ply of both players, so the player that is to move gets to redo its
+    /// previous move. This is the usual takeback when playing against the computer.
+    pub fn take_back_full_move(&mut self, allow_finished: bool) -> Result<()> {
+        if self.moves.len() < 2 {
+            return Err(QuoridorError::NoMoveToTakeBack);
+        }
+        self.take_back_ply(allow_finished)?;
+        self.take_back_ply(false)
+    }
+
+    fn create_snapshot(&self, undo: UndoAction) -> PlySnapshot {
+        PlySnapshot {
+            undo,
+            white_time_used: self.white_time_used,
+            black_time_used: self.black_time_used,
+        }
+    }
+
+    /// Ends the game for a reason that doesn't follow from the board, like a resignation.
+    pub(crate) fn end_game(&mut self, won_by: Player, reason: VictoryReason) {
+        self.status = GameStatus::Finished { won_by, reason };
+    }
+
+    /// The total time on the clock of the player over the whole game, which is the starting time
+    /// plus the increment for every move the player made.
+    fn get_time_available(&self, player: Player) -> Option<Duration> {
+        let TimeControl::Timed { seconds, increment } = self.time_control else {
+            return None;
+        };
+        let moves_made = self
+            .moves
+            .iter()
+            .filter(|executed_action| {
+                executed_action.get_player() == player
+                    && !matches!(executed_action.get_action(), Action::Resigned(_))
+            })
+            .count();
+
+        Some(Duration::from_secs(
+            (seconds + increment * moves_made) as u64,
+        ))
+    }
+
+    /// Ends the game on time, the clock of the player is stopped at the moment the time ran out.
+    fn flag(&mut self, player: Player) {
+        let time_used = self
+            .get_time_available(player)
+            .expect("Only timed games can be lost on time");
+        match player {
+            Player::White => self.white_time_used = time_used,
+            Player::Black => self.black_time_used = time_used,
+        }
+        self.end_game(player.get_opponent(), VictoryReason::OutOffTime);
+    }
+}
+
+/// The state before a ply was executed that is not stored on the board, used to take back plies.
+struct PlySnapshot {
+    undo: UndoAction,
+    white_time_used: Duration,
+    black_time_used: Duration,
 }
 
-#[derive(Debug)]
+/// A source for the current time, used by the gamestate to keep track of the clocks.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The clock used for real games, it reads the time from the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves forward when it is advanced, to test time controls without having to
+/// wait for the time to pass.
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self
+            .now
+            .lock()
+            .expect("The clock lock should never be poisoned") += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self
+            .now
+            .lock()
+            .expect("The clock lock should never be poisoned")
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TimeControl {
     Timed { seconds: usize, increment: usize },
     Correspondence,
+    Unlimited,
+}
+
+impl TimeControl {
+    /// Reads a time control written as `seconds+increment`, `correspondence`, or `-` when there
+    /// is no time limit.
+    pub fn from_notation(notation: &str) -> Result<TimeControl> {
+        match notation {
+            "correspondence" => Ok(TimeControl::Correspondence),
+            "-" => Ok(TimeControl::Unlimited),
+            _ => {
+                if let Some((seconds, increment)) = notation.split_once('+') {
+                    if let (Ok(seconds), Ok(increment)) = (seconds.parse(), increment.parse()) {
+                        return Ok(TimeControl::Timed { seconds, increment });
+                    }
+                }
+                Err(QuoridorError::InvalidNotation {
+                    notation: String::from(notation),
+                    reason: String::from(
+                        "The time control should be seconds+increment, correspondence or -",
+                    ),
+                })
+            }
+        }
+    }
+
+    pub fn get_notation(&self) -> String {
+        match self {
+            TimeControl::Timed { seconds, increment } => format!("{seconds}+{increment}"),
+            TimeControl::Correspondence => String::from("correspondence"),
+            TimeControl::Unlimited => String::from("-"),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -66,3 +408,350 @@ pub enum VictoryReason {
     OutOffTime,
     Abandoned,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed_gamestate(seconds: usize, increment: usize) -> (Gamestate, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new());
+        let gamestate =
+            Gamestate::with_clock(TimeControl::Timed { seconds, increment }, clock.clone());
+        (gamestate, clock)
+    }
+
+    #[test]
+    fn time_is_charged_to_the_mover() {
+        let (mut gamestate, clock) = timed_gamestate(60, 0);
+        clock.advance(Duration::from_secs(5));
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        clock.advance(Duration::from_secs(3));
+
+        assert_eq!(
+            gamestate.get_remaining_time(Player::White),
+            Some(Duration::from_secs(55))
+        );
+        assert_eq!(
+            gamestate.get_remaining_time(Player::Black),
+            Some(Duration::from_secs(57))
+        );
+        assert_eq!(
+            gamestate.get_time_used(Player::White),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn increment_is_added_after_move() {
+        let (mut gamestate, clock) = timed_gamestate(60, 2);
+        clock.advance(Duration::from_secs(5));
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        assert_eq!(
+            gamestate.get_remaining_time(Player::White),
+            Some(Duration::from_secs(57))
+        );
+        assert_eq!(
+            gamestate.get_remaining_time(Player::Black),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn move_after_flag_loses_on_time() {
+        let (mut gamestate, clock) = timed_gamestate(60, 0);
+        clock.advance(Duration::from_secs(61));
+        let status = gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+
+        assert_eq!(
+            status,
+            GameStatus::Finished {
+                won_by: Player::Black,
+                reason: VictoryReason::OutOffTime
+            }
+        );
+        assert!(gamestate.get_moves().is_empty());
+        assert_eq!(
+            gamestate.get_remaining_time(Player::White),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(
+            gamestate.get_time_used(Player::White),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn check_flag_without_move() {
+        let (mut gamestate, clock) = timed_gamestate(60, 0);
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(gamestate.check_flag(), GameStatus::InProgress);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            gamestate.check_flag(),
+            GameStatus::Finished {
+                won_by: Player::White,
+                reason: VictoryReason::OutOffTime
+            }
+        );
+    }
+
+    #[test]
+    fn received_action_is_charged_until_receipt() {
+        let (mut gamestate, clock) = timed_gamestate(60, 0);
+        clock.advance(Duration::from_secs(5));
+        let received_at = clock.now();
+        clock.advance(Duration::from_secs(2));
+        gamestate
+            .execute_received_action(
+                Player::White,
+                Action::from_notation("E2").unwrap(),
+                received_at,
+            )
+            .unwrap();
+        assert_eq!(
+            gamestate.get_time_used(Player::White),
+            Duration::from_secs(5)
+        );
+
+        // A move received just before the flag is still in time.
+        clock.advance(Duration::from_secs(59));
+        let received_at = clock.now();
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(
+            gamestate
+                .execute_received_action(
+                    Player::Black,
+                    Action::from_notation("E8").unwrap(),
+                    received_at,
+                )
+                .unwrap(),
+            GameStatus::InProgress
+        );
+        assert_eq!(
+            gamestate.get_remaining_time(Player::Black),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn time_spent_on_received_actions_adds_up_to_the_time_used() {
+        let (mut gamestate, clock) = timed_gamestate(60, 0);
+        let players = [Player::White, Player::Black];
+        for (ply, notation) in ["E2", "E8", "E3", "E7"].into_iter().enumerate() {
+            clock.advance(Duration::from_secs(3));
+            let received_at = clock.now();
+            // Waiting to be executed is not charged to either player.
+            clock.advance(Duration::from_secs(1));
+            gamestate
+                .execute_received_action(
+                    players[ply % 2],
+                    Action::from_notation(notation).unwrap(),
+                    received_at,
+                )
+                .unwrap();
+        }
+
+        for player in players {
+            let time_spent: Duration = gamestate
+                .get_moves()
+                .iter()
+                .filter(|executed_action| executed_action.get_player() == player)
+                .map(|executed_action| executed_action.get_time_spent())
+                .sum();
+            assert_eq!(time_spent, gamestate.get_time_used(player));
+            assert_eq!(time_spent, Duration::from_secs(6));
+        }
+    }
+
+    #[test]
+    fn untimed_game_has_no_remaining_time() {
+        let clock = Arc::new(ManualClock::new());
+        let mut gamestate = Gamestate::with_clock(TimeControl::Correspondence, clock.clone());
+        clock.advance(Duration::from_secs(60 * 60 * 24));
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        assert_eq!(gamestate.get_remaining_time(Player::White), None);
+        assert_eq!(gamestate.get_status(), GameStatus::InProgress);
+    }
+
+    #[test]
+    fn take_back_ply_restores_board_and_clocks() {
+        let (mut gamestate, clock) = timed_gamestate(60, 2);
+        clock.advance(Duration::from_secs(5));
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        clock.advance(Duration::from_secs(7));
+        gamestate
+            .execute_action(Action::from_notation("D8h").unwrap())
+            .unwrap();
+        clock.advance(Duration::from_secs(4));
+
+        gamestate.take_back_ply(false).unwrap();
+        assert_eq!(gamestate.get_moves().len(), 1);
+        assert_eq!(
+            gamestate.get_boardstate().get_active_player(),
+            Player::Black
+        );
+        assert_eq!(
+            gamestate
+                .get_boardstate()
+                .get_available_walls_black_player(),
+            10
+        );
+        assert_eq!(
+            gamestate.get_remaining_time(Player::Black),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            gamestate.get_remaining_time(Player::White),
+            Some(Duration::from_secs(57))
+        );
+    }
+
+    #[test]
+    fn take_back_full_move() {
+        let mut gamestate = Gamestate::new(TimeControl::Unlimited);
+        for notation in ["E2", "E8", "E3"] {
+            gamestate
+                .execute_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+        gamestate.take_back_full_move(false).unwrap();
+        assert_eq!(gamestate.get_moves().len(), 1);
+        assert_eq!(
+            gamestate.get_boardstate().get_active_player(),
+            Player::Black
+        );
+        assert_eq!(
+            gamestate.get_boardstate().get_position_black_pawn(),
+            Boardstate::new().get_position_black_pawn()
+        );
+
+        assert!(gamestate.take_back_full_move(false).is_err());
+        gamestate.take_back_ply(false).unwrap();
+        assert_eq!(gamestate.get_boardstate(), &Boardstate::new());
+        assert!(gamestate.take_back_ply(false).is_err());
+    }
+
+    #[test]
+    fn take_back_after_game_finished() {
+        let (mut gamestate, clock) = timed_gamestate(60, 0);
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        clock.advance(Duration::from_secs(61));
+        assert_ne!(gamestate.check_flag(), GameStatus::InProgress);
+
+        assert!(gamestate.take_back_ply(false).is_err());
+        gamestate.take_back_ply(true).unwrap();
+        assert_eq!(gamestate.get_status(), GameStatus::InProgress);
+        assert_eq!(
+            gamestate.get_remaining_time(Player::Black),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            gamestate.get_remaining_time(Player::White),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn no_actions_after_game_finished() {
+        let mut gamestate = Gamestate::new(TimeControl::Unlimited);
+        assert_eq!(
+            gamestate.resign(Player::Black).unwrap(),
+            GameStatus::Finished {
+                won_by: Player::White,
+                reason: VictoryReason::Resigned
+            }
+        );
+        assert_eq!(
+            gamestate
+                .execute_action(Action::from_notation("E2").unwrap())
+                .unwrap_err(),
+            QuoridorError::GameFinished
+        );
+        assert_eq!(
+            gamestate.resign(Player::White).unwrap_err(),
+            QuoridorError::GameFinished
+        );
+        assert_eq!(
+            gamestate.abandon(Player::White).unwrap_err(),
+            QuoridorError::GameFinished
+        );
+    }
+
+    #[test]
+    fn execute_action_for_validates_player() {
+        let mut gamestate = Gamestate::new(TimeControl::Unlimited);
+        assert_eq!(
+            gamestate
+                .execute_action_for(Player::Black, Action::from_notation("E8").unwrap())
+                .unwrap_err(),
+            QuoridorError::NotYourTurn {
+                player: Player::Black
+            }
+        );
+        assert_eq!(
+            gamestate
+                .execute_action_for(Player::Black, Action::Resigned(Player::White))
+                .unwrap_err(),
+            QuoridorError::ResignForOpponent {
+                player: Player::Black
+            }
+        );
+        gamestate
+            .execute_action_for(Player::White, Action::from_notation("E2").unwrap())
+            .unwrap();
+        assert_eq!(
+            gamestate
+                .execute_action_for(Player::White, Action::Resigned(Player::White))
+                .unwrap(),
+            GameStatus::Finished {
+                won_by: Player::Black,
+                reason: VictoryReason::Resigned
+            }
+        );
+    }
+
+    #[test]
+    fn abandoned_game_is_won_by_opponent() {
+        let mut gamestate = Gamestate::new(TimeControl::Unlimited);
+        assert_eq!(
+            gamestate.abandon(Player::White).unwrap(),
+            GameStatus::Finished {
+                won_by: Player::Black,
+                reason: VictoryReason::Abandoned
+            }
+        );
+    }
+
+    #[test]
+    fn time_control_notation() {
+        for time_control in [
+            TimeControl::Timed {
+                seconds: 300,
+                increment: 5,
+            },
+            TimeControl::Correspondence,
+            TimeControl::Unlimited,
+        ] {
+            assert_eq!(
+                TimeControl::from_notation(&time_control.get_notation()).unwrap(),
+                time_control
+            );
+        }
+        assert!(TimeControl::from_notation("5 minutes").is_err());
+    }
+}
@@ -1,5 +1,8 @@
 pub mod actions;
 pub mod boardstate;
+pub mod error;
 pub mod gamestate;
 pub mod locations;
+pub mod record;
 pub mod visualize;
+mod zobrist;
@@ -1,4 +1,4 @@
-use anyhow::{bail, ensure, Result};
+use crate::error::{QuoridorError, Result};
 
 pub trait Location {
     fn get_square(&self) -> u8;
@@ -25,21 +25,19 @@ impl PawnLocation {
     ///
     /// The square number must be between 1 and 81, or else an error will be returned.
     pub fn build(square: u8) -> Result<Self> {
-        ensure!(
-            (0..=80).contains(&square),
-            format!("The square should be in range 0..=80, but was: {square}")
-        );
+        if !(0..=80).contains(&square) {
+            return Err(QuoridorError::SquareOutOfRange { square });
+        }
         Ok(PawnLocation { square })
     }
 
     pub fn from_coordinate(coordinate: Coordinate) -> Result<Self> {
-        ensure!(
-            coordinate.x < 9 && coordinate.y < 9,
-            format!(
-                "The coordinate x and y should be in the range 0..=8, but where x: {} y: {}",
-                coordinate.x, coordinate.y
-            )
-        );
+        if coordinate.x >= 9 || coordinate.y >= 9 {
+            return Err(QuoridorError::CoordinateOutOfRange {
+                x: coordinate.x,
+                y: coordinate.y,
+            });
+        }
         Ok(PawnLocation {
             square: coordinate.to_square(),
         })
@@ -49,7 +47,10 @@ impl PawnLocation {
         if let Some(new_coordinate) = self.get_coordinate().from_direction(direction) {
             return PawnLocation::from_coordinate(new_coordinate);
         }
-        bail!("Can't create new location from direction")
+        Err(QuoridorError::OffTheBoard {
+            square: self.square,
+            direction,
+        })
     }
 }
 
@@ -72,12 +73,9 @@ impl WallLocation {
     /// but the node value can be between 1 and 71. This is because the coordinate of the squares
     /// is
     pub fn build(square: u8, orientation: WallOrientation) -> Result<Self> {
-        ensure!(
-            (0..=70).contains(&square) && &square % 9 != 8,
-            format!(
-                "The square should be in the range 0..=70 and should not be divisible by 9, but was {}", 
-                square
-            ));
+        if !(0..=70).contains(&square) || square % 9 == 8 {
+            return Err(QuoridorError::SquareOutOfRange { square });
+        }
         Ok(WallLocation {
             square,
             orientation,
@@ -85,13 +83,12 @@ impl WallLocation {
     }
 
     pub fn from_coordinate(coordinate: Coordinate, orientation: WallOrientation) -> Result<Self> {
-        ensure!(
-            coordinate.x < 8 && coordinate.y < 8,
-            format!(
-                "The coordinate x and y should be in the range 0..=7, but where x: {} y: {}",
-                coordinate.x, coordinate.y
-            )
-        );
+        if coordinate.x >= 8 || coordinate.y >= 8 {
+            return Err(QuoridorError::CoordinateOutOfRange {
+                x: coordinate.x,
+                y: coordinate.y,
+            });
+        }
         Ok(WallLocation {
             square: coordinate.to_square(),
             orientation,
@@ -99,7 +96,7 @@ impl WallLocation {
     }
 
     pub fn get_orientation(&self) -> WallOrientation {
-        self.orientation.clone()
+        self.orientation
     }
 }
 
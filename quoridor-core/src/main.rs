@@ -1,8 +1,8 @@
-use anyhow::Result;
+use quoridor_core::error::Result;
 
 use quoridor_core::actions::Action;
 use quoridor_core::gamestate::Gamestate;
-use quoridor_core::locations::{Coordinate, PawnLocation, WallLocation, WallOrientation};
+use quoridor_core::locations::{Coordinate, PawnLocation};
 use quoridor_core::visualize::{print_action, print_board_state};
 
 fn main() -> Result<()> {
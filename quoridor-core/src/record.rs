@@ -0,0 +1,515 @@
+use crate::actions::Action;
+use crate::boardstate::{Boardstate, Player};
+use crate::error::{QuoridorError, Result};
+use crate::gamestate::{GameStatus, Gamestate, TimeControl, VictoryReason};
+
+/// A record of a played game that can be written to and read from text, inspired by the PGN
+/// format used for chess.
+///
+/// A record starts with tag headers describing the game, followed by the numbered move pairs in
+/// the same notation as `Action::get_notation` and the result of the game:
+///
+/// ```text
+/// [White "Alice"]
+/// [Black "Bob"]
+/// [Date "2024.11.20"]
+/// [TimeControl "300+5"]
+/// [Result "1-0"]
+/// [Reason "Resigned"]
+///
+/// 1. E2 E8 2. E3 E7 {+1.00} 3. E4v 1-0
+/// ```
+///
+/// A move can be followed by a comment in braces, for example the evaluation of an analysis. The
+/// time control is written as `seconds+increment`, `correspondence` or `-` for unlimited. The
+/// result is `1-0` when white won, `0-1` when black won or `*` while the game is in progress.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GameRecord {
+    white: String,
+    black: String,
+    date: String,
+    time_control: TimeControl,
+    status: GameStatus,
+    actions: Vec<Action>,
+    /// The comment after every action, None for actions without a comment.
+    comments: Vec<Option<String>>,
+}
+
+impl GameRecord {
+    /// Creates a record from the moves played in the gamestate, a resignation is not part of the
+    /// moves but is stored in the result.
+    pub fn from_gamestate(gamestate: &Gamestate, white: &str, black: &str, date: &str) -> Self {
+        let actions: Vec<Action> = gamestate
+            .get_moves()
+            .iter()
+            .map(|executed_action| executed_action.get_action())
+            .filter(|action| !matches!(action, Action::Resigned(_)))
+            .collect();
+        GameRecord {
+            white: String::from(white),
+            black: String::from(black),
+            date: String::from(date),
+            time_control: gamestate.get_time_control(),
+            status: gamestate.get_status(),
+            comments: vec![None; actions.len()],
+            actions,
+        }
+    }
+
+    /// Reads a record from text. All the moves are replayed on a board, so a record with an
+    /// illegal move is rejected with the ply number and the reason the move is illegal.
+    pub fn from_record_notation(notation: &str) -> Result<Self> {
+        let mut white = String::from("?");
+        let mut black = String::from("?");
+        let mut date = String::from("?");
+        let mut time_control = TimeControl::Unlimited;
+        let mut result = String::from("*");
+        let mut reason: Option<VictoryReason> = None;
+
+        let mut movetext = String::new();
+        for line in notation.lines().map(str::trim) {
+            if line.starts_with('[') {
+                let (name, value) = tag_from_notation(line)?;
+                match name.as_str() {
+                    "White" => white = value,
+                    "Black" => black = value,
+                    "Date" => date = value,
+                    "TimeControl" => time_control = TimeControl::from_notation(&value)?,
+                    "Result" => result = value,
+                    "Reason" => reason = Some(victory_reason_from_notation(&value)?),
+                    _ => (),
+                }
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+
+        let (actions, comments, result_token) = actions_from_movetext(&movetext)?;
+        if let Some(result_token) = result_token {
+            if result_token != result {
+                return Err(invalid_record(format!(
+                    "The result {result_token} after the moves doesn't match the Result tag {result}"
+                )));
+            }
+        }
+
+        let status = match (result.as_str(), reason) {
+            ("*", None) => GameStatus::InProgress,
+            ("1-0", Some(reason)) => GameStatus::Finished {
+                won_by: Player::White,
+                reason,
+            },
+            ("0-1", Some(reason)) => GameStatus::Finished {
+                won_by: Player::Black,
+                reason,
+            },
+            ("*", Some(_)) => {
+                return Err(invalid_record(String::from(
+                    "A game in progress can't have a Reason tag",
+                )))
+            }
+            ("1-0" | "0-1", None) => {
+                return Err(invalid_record(String::from(
+                    "A finished game needs a Reason tag",
+                )))
+            }
+            (other, _) => {
+                return Err(invalid_notation(
+                    other,
+                    "The Result tag should be 1-0, 0-1 or *",
+                ))
+            }
+        };
+
+        let record = GameRecord {
+            white,
+            black,
+            date,
+            time_control,
+            status,
+            actions,
+            comments,
+        };
+        record.replay()?;
+
+        Ok(record)
+    }
+
+    /// Writes the record as text that can be read back with `from_record_notation`.
+    pub fn get_record_notation(&self) -> String {
+        let mut notation = String::new();
+        notation.push_str(&tag_to_notation("White", &self.white));
+        notation.push_str(&tag_to_notation("Black", &self.black));
+        notation.push_str(&tag_to_notation("Date", &self.date));
+        notation.push_str(&tag_to_notation(
+            "TimeControl",
+            &self.time_control.get_notation(),
+        ));
+        notation.push_str(&tag_to_notation("Result", &self.get_result_notation()));
+        if let GameStatus::Finished { reason, .. } = self.status {
+            notation.push_str(&tag_to_notation("Reason", &format!("{:?}", reason)));
+        }
+        notation.push('\n');
+
+        let mut movetext: Vec<String> = Vec::with_capacity(self.actions.len() * 3 / 2 + 1);
+        for (ply, action) in self.actions.iter().enumerate() {
+            if ply.is_multiple_of(2) {
+                movetext.push(format!("{}.", ply / 2 + 1));
+            }
+            movetext.push(action.get_notation());
+            if let Some(comment) = &self.comments[ply] {
+                movetext.push(format!("{{{comment}}}"));
+            }
+        }
+        movetext.push(self.get_result_notation());
+        notation.push_str(&movetext.join(" "));
+        notation.push('\n');
+
+        notation
+    }
+
+    /// Replays the moves of the record into a new gamestate, so the game can be continued or
+    /// stepped through.
+    pub fn to_gamestate(&self) -> Result<Gamestate> {
+        let mut gamestate = Gamestate::new(self.time_control);
+        for (ply, action) in self.actions.iter().enumerate() {
+            gamestate
+                .execute_action(*action)
+                .map_err(|error| illegal_move_error(ply, action, error))?;
+        }
+        if let GameStatus::Finished { won_by, reason } = self.status {
+            if gamestate.get_status() == GameStatus::InProgress {
+                gamestate.end_game(won_by, reason);
+            }
+        }
+
+        Ok(gamestate)
+    }
+
+    pub fn get_white(&self) -> &str {
+        &self.white
+    }
+
+    pub fn get_black(&self) -> &str {
+        &self.black
+    }
+
+    pub fn get_date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn get_time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    pub fn get_status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn get_actions(&self) -> &Vec<Action> {
+        &self.actions
+    }
+
+    /// The comment after the action at the ply, counting from 0.
+    pub fn get_comment(&self, ply: usize) -> Option<&str> {
+        self.comments.get(ply)?.as_deref()
+    }
+
+    /// Sets the comment after the action at the ply, counting from 0. A `}` would end the
+    /// comment early, so it is replaced by `)`. Returns false when there is no action at the ply.
+    pub fn set_comment(&mut self, ply: usize, comment: &str) -> bool {
+        match self.comments.get_mut(ply) {
+            Some(slot) => {
+                *slot = Some(comment.replace('}', ")"));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn get_result_notation(&self) -> String {
+        match self.status {
+            GameStatus::InProgress => String::from("*"),
+            GameStatus::Finished {
+                won_by: Player::White,
+                ..
+            } => String::from("1-0"),
+            GameStatus::Finished {
+                won_by: Player::Black,
+                ..
+            } => String::from("0-1"),
+        }
+    }
+
+    /// Checks that every move is legal and that the final position agrees with the result.
+    fn replay(&self) -> Result<()> {
+        let mut boardstate = Boardstate::new();
+        let mut status = GameStatus::InProgress;
+        for (ply, action) in self.actions.iter().enumerate() {
+            if status != GameStatus::InProgress {
+                return Err(illegal_move_error(ply, action, QuoridorError::GameFinished));
+            }
+            status = boardstate
+                .apply_action(*action)
+                .map_err(|error| illegal_move_error(ply, action, error))?;
+        }
+
+        match (status, self.status) {
+            (GameStatus::InProgress, GameStatus::InProgress) => Ok(()),
+            (
+                GameStatus::InProgress,
+                GameStatus::Finished {
+                    reason: VictoryReason::ReachedOppositeSide,
+                    ..
+                },
+            ) => Err(invalid_record(String::from(
+                "The Reason tag says a pawn reached the opposite side, but no pawn did",
+            ))),
+            (GameStatus::InProgress, GameStatus::Finished { .. }) => Ok(()),
+            (board_status, record_status) => {
+                if board_status != record_status {
+                    return Err(invalid_record(format!(
+                        "The moves end with {:?}, but the tags say {:?}",
+                        board_status, record_status
+                    )));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn illegal_move_error(ply: usize, action: &Action, error: QuoridorError) -> QuoridorError {
+    QuoridorError::IllegalRecordMove {
+        ply: ply + 1,
+        notation: action.get_notation(),
+        reason: Box::new(error),
+    }
+}
+
+fn invalid_notation(notation: &str, reason: &str) -> QuoridorError {
+    QuoridorError::InvalidNotation {
+        notation: String::from(notation),
+        reason: String::from(reason),
+    }
+}
+
+fn invalid_record(reason: String) -> QuoridorError {
+    QuoridorError::InvalidRecord { reason }
+}
+
+type Movetext = (Vec<Action>, Vec<Option<String>>, Option<String>);
+
+/// Splits the movetext into the actions, the comments after the actions and the optional result
+/// at the end, the move numbers are checked to be in order.
+fn actions_from_movetext(movetext: &str) -> Result<Movetext> {
+    let mut actions: Vec<Action> = Vec::new();
+    let mut comments: Vec<Option<String>> = Vec::new();
+    let mut result: Option<String> = None;
+
+    let mut rest = movetext.trim_start();
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix('{') {
+            let (comment, after) = comment
+                .split_once('}')
+                .ok_or_else(|| invalid_notation(rest, "A comment should end with }"))?;
+            let Some(slot) = comments.last_mut() else {
+                return Err(invalid_notation(comment, "A comment should follow a move"));
+            };
+            *slot = Some(String::from(comment.trim()));
+            rest = after.trim_start();
+            continue;
+        }
+        let end = rest
+            .find(|char: char| char.is_whitespace() || char == '{')
+            .unwrap_or(rest.len());
+        let (token, after) = rest.split_at(end);
+        rest = after.trim_start();
+
+        if result.is_some() {
+            return Err(invalid_notation(
+                token,
+                "Found a move after the result of the game",
+            ));
+        }
+        if let Some(number) = token.strip_suffix('.') {
+            let expected = actions.len() / 2 + 1;
+            if !actions.len().is_multiple_of(2) || number.parse::<usize>().ok() != Some(expected) {
+                return Err(invalid_notation(
+                    token,
+                    &format!("Expected move number {expected}."),
+                ));
+            }
+        } else if matches!(token, "1-0" | "0-1" | "*") {
+            result = Some(String::from(token));
+        } else {
+            let action = Action::from_notation(token).map_err(|error| {
+                invalid_notation(token, &format!("at ply {}: {}", actions.len() + 1, error))
+            })?;
+            actions.push(action);
+            comments.push(None);
+        }
+    }
+
+    Ok((actions, comments, result))
+}
+
+fn tag_to_notation(name: &str, value: &str) -> String {
+    format!(
+        "[{} \"{}\"]\n",
+        name,
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+fn tag_from_notation(line: &str) -> Result<(String, String)> {
+    let content = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(|| invalid_notation(line, "A tag should be enclosed in []"))?;
+    let (name, value) = content
+        .split_once(' ')
+        .ok_or_else(|| invalid_notation(line, "A tag needs a name and a value"))?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(|| invalid_notation(line, "The tag value should be quoted"))?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char == '\\' {
+            if let Some(escaped) = chars.next() {
+                unescaped.push(escaped);
+            }
+        } else {
+            unescaped.push(char);
+        }
+    }
+
+    Ok((String::from(name), unescaped))
+}
+
+fn victory_reason_from_notation(notation: &str) -> Result<VictoryReason> {
+    match notation {
+        "ReachedOppositeSide" => Ok(VictoryReason::ReachedOppositeSide),
+        "Resigned" => Ok(VictoryReason::Resigned),
+        "OutOffTime" => Ok(VictoryReason::OutOffTime),
+        "Abandoned" => Ok(VictoryReason::Abandoned),
+        _ => Err(invalid_notation(notation, "Unknown Reason tag")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(notations: &[&str]) -> Gamestate {
+        let mut gamestate = Gamestate::new(TimeControl::Timed {
+            seconds: 300,
+            increment: 5,
+        });
+        for notation in notations {
+            gamestate
+                .execute_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+        gamestate
+    }
+
+    #[test]
+    fn record_notation_round_trip() {
+        let gamestate = play(&["E2", "E8", "C3h", "F6v", "D2"]);
+        let record = GameRecord::from_gamestate(&gamestate, "Alice \"A\"", "Bob", "2024.11.20");
+        let notation = record.get_record_notation();
+        assert_eq!(
+            notation,
+            "[White \"Alice \\\"A\\\"\"]\n[Black \"Bob\"]\n[Date \"2024.11.20\"]\n\
+             [TimeControl \"300+5\"]\n[Result \"*\"]\n\n1. E2 E8 2. C3h F6v 3. D2 *\n"
+        );
+
+        let parsed = GameRecord::from_record_notation(&notation).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.get_white(), "Alice \"A\"");
+        assert_eq!(
+            parsed.to_gamestate().unwrap().get_boardstate(),
+            gamestate.get_boardstate()
+        );
+    }
+
+    #[test]
+    fn record_notation_finished_game() {
+        let gamestate = play(&[
+            "E2", "E8", "E3", "E7", "E4", "E6", "E5", "E4", "E6", "E3", "E7", "E2", "E8", "E1",
+        ]);
+        let record = GameRecord::from_gamestate(&gamestate, "Alice", "Bob", "?");
+        let notation = record.get_record_notation();
+        assert!(notation.contains("[Reason \"ReachedOppositeSide\"]"));
+        assert!(notation.ends_with("7. E8 E1 0-1\n"));
+        assert_eq!(GameRecord::from_record_notation(&notation).unwrap(), record);
+    }
+
+    #[test]
+    fn record_notation_resigned_game() {
+        let notation = "[White \"Alice\"]\n[Black \"Bob\"]\n[Result \"0-1\"]\n\
+                        [Reason \"Resigned\"]\n\n1. E2 E8 2. C3h 0-1\n";
+        let record = GameRecord::from_record_notation(notation).unwrap();
+        assert_eq!(record.get_actions().len(), 3);
+        assert_eq!(record.get_time_control(), TimeControl::Unlimited);
+        assert_eq!(
+            record.to_gamestate().unwrap().get_status(),
+            GameStatus::Finished {
+                won_by: Player::Black,
+                reason: VictoryReason::Resigned
+            }
+        );
+    }
+
+    #[test]
+    fn record_notation_comments() {
+        let gamestate = play(&["E2", "E8", "C3h"]);
+        let mut record = GameRecord::from_gamestate(&gamestate, "Alice", "Bob", "?");
+        assert!(record.set_comment(1, "+0.00 {best}"));
+        assert!(record.set_comment(2, "blunder"));
+        assert!(!record.set_comment(3, "no move"));
+
+        let notation = record.get_record_notation();
+        assert!(notation.ends_with("1. E2 E8 {+0.00 {best)} 2. C3h {blunder} *\n"));
+        let parsed = GameRecord::from_record_notation(&notation).unwrap();
+        assert_eq!(parsed, record);
+        assert_eq!(parsed.get_comment(0), None);
+        assert_eq!(parsed.get_comment(1), Some("+0.00 {best)"));
+
+        let parsed = GameRecord::from_record_notation("1. E2{first} E8 {second\nline} *").unwrap();
+        assert_eq!(parsed.get_comment(0), Some("first"));
+        // The lines of the movetext are joined, like the lines of a comment.
+        assert_eq!(parsed.get_comment(1), Some("second line"));
+    }
+
+    #[test]
+    fn record_notation_illegal_move() {
+        let notation = "[Result \"*\"]\n\n1. E2 E8 2. E4 *\n";
+        let error = GameRecord::from_record_notation(notation).unwrap_err();
+        assert!(error.to_string().starts_with("Illegal move E4 at ply 3"));
+    }
+
+    #[test]
+    fn record_notation_failed() {
+        let inputs = [
+            "1. E2 E8 3. E3 *",
+            "1. E2 E8 2. X3 *",
+            "[Result \"1-0\"]\n\n1. E2 E8 1-0",
+            "[Result \"1-0\"]\n[Reason \"ReachedOppositeSide\"]\n\n1. E2 E8 1-0",
+            "[Result \"*\"]\n\n1. E2 E8 1-0",
+            "[TimeControl \"fast\"]\n\n1. E2 *",
+            "[Result \"*\"]\n\n1. E2 * E8",
+            "1. E2 {unfinished *",
+            "{before} 1. E2 *",
+        ];
+        for input in inputs {
+            assert!(GameRecord::from_record_notation(input).is_err(), "{input}");
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Random keys used to calculate the Zobrist hash of a boardstate.
+//!
+//! Every feature of a position (a pawn on a square, a wall with an orientation, the number of
+//! walls a player has left and the side to move) gets its own random 64 bit key. The hash of a
+//! position is the xor of the keys of all its features, which means that the hash can be updated
+//! incrementally by xor-ing the keys of the features that change when an action is applied.
+//!
+//! The keys are generated at compile time with a fixed seed, so the hash of a position is the
+//! same between runs and can be stored.
+
+use crate::boardstate::Player;
+use crate::locations::WallOrientation;
+
+const SEED: u64 = 0x5155_4f52_4944_4f52;
+
+const PAWN_KEYS_OFFSET: u64 = 0;
+const WALL_KEYS_OFFSET: u64 = PAWN_KEYS_OFFSET + 2 * 81;
+const AVAILABLE_WALLS_KEYS_OFFSET: u64 = WALL_KEYS_OFFSET + 2 * 71;
+const BLACK_TO_MOVE_KEY_OFFSET: u64 = AVAILABLE_WALLS_KEYS_OFFSET + 2 * 11;
+
+const PAWN_KEYS: [[u64; 81]; 2] = [
+    generate_keys::<81>(PAWN_KEYS_OFFSET),
+    generate_keys::<81>(PAWN_KEYS_OFFSET + 81),
+];
+
+const WALL_KEYS: [[u64; 71]; 2] = [
+    generate_keys::<71>(WALL_KEYS_OFFSET),
+    generate_keys::<71>(WALL_KEYS_OFFSET + 71),
+];
+
+const AVAILABLE_WALLS_KEYS: [[u64; 11]; 2] = [
+    generate_keys::<11>(AVAILABLE_WALLS_KEYS_OFFSET),
+    generate_keys::<11>(AVAILABLE_WALLS_KEYS_OFFSET + 11),
+];
+
+const BLACK_TO_MOVE_KEY: u64 = generate_keys::<1>(BLACK_TO_MOVE_KEY_OFFSET)[0];
+
+/// The SplitMix64 generator, every index in the sequence gives an independent random number.
+const fn splitmix64(index: u64) -> u64 {
+    let mut z = index.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn generate_keys<const N: usize>(offset: u64) -> [u64; N] {
+    let mut keys = [0u64; N];
+    let mut index = 0;
+    while index < N {
+        keys[index] = splitmix64(SEED.wrapping_add(offset + index as u64 + 1));
+        index += 1;
+    }
+    keys
+}
+
+fn player_index(player: Player) -> usize {
+    match player {
+        Player::White => 0,
+        Player::Black => 1,
+    }
+}
+
+pub(crate) fn pawn_key(player: Player, square: u8) -> u64 {
+    PAWN_KEYS[player_index(player)][usize::from(square)]
+}
+
+pub(crate) fn wall_key(orientation: WallOrientation, square: u8) -> u64 {
+    match orientation {
+        WallOrientation::Horizontal => WALL_KEYS[0][usize::from(square)],
+        WallOrientation::Vertical => WALL_KEYS[1][usize::from(square)],
+    }
+}
+
+pub(crate) fn available_walls_key(player: Player, available_walls: u8) -> u64 {
+    AVAILABLE_WALLS_KEYS[player_index(player)][usize::from(available_walls)]
+}
+
+pub(crate) fn active_player_key(player: Player) -> u64 {
+    match player {
+        Player::White => 0,
+        Player::Black => BLACK_TO_MOVE_KEY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn all_keys_are_unique() {
+        let mut keys: HashSet<u64> = HashSet::new();
+        for player_keys in PAWN_KEYS {
+            keys.extend(player_keys);
+        }
+        for orientation_keys in WALL_KEYS {
+            keys.extend(orientation_keys);
+        }
+        for player_keys in AVAILABLE_WALLS_KEYS {
+            keys.extend(player_keys);
+        }
+        keys.insert(BLACK_TO_MOVE_KEY);
+        assert_eq!(keys.len(), 2 * 81 + 2 * 71 + 2 * 11 + 1);
+        assert!(!keys.contains(&0));
+    }
+}
@@ -0,0 +1,390 @@
+//! Drives an engine that speaks the QEI protocol, usually an external engine process.
+
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, Command as Process, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use quoridor_core::actions::Action;
+use quoridor_core::error::QuoridorError;
+
+use crate::protocol::{Command, EngineOption, GoParameters, Position, Response, SearchInfo};
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// Writing to the engine failed, or the engine could not be started.
+    Io(io::Error),
+    /// The engine sent a line that is not a QEI response.
+    InvalidResponse(QuoridorError),
+    /// The engine did not answer in time.
+    Timeout,
+    /// The engine closed its output, it probably exited.
+    Disconnected,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Io(error) => write!(f, "Communication with the engine failed: {error}"),
+            ClientError::InvalidResponse(error) => {
+                write!(f, "The engine sent an invalid response: {error}")
+            }
+            ClientError::Timeout => write!(f, "The engine did not answer in time"),
+            ClientError::Disconnected => write!(f, "The engine disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(error: io::Error) -> Self {
+        ClientError::Io(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// What the engine told about itself in the answer to `qei`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EngineDetails {
+    name: Option<String>,
+    author: Option<String>,
+    options: Vec<EngineOption>,
+}
+
+impl EngineDetails {
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn get_options(&self) -> &Vec<EngineOption> {
+        &self.options
+    }
+}
+
+/// The answer of the engine to `go`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOutcome {
+    best_action: Option<Action>,
+    last_info: Option<SearchInfo>,
+}
+
+impl SearchOutcome {
+    /// The action the engine wants to play, None when it had no legal action.
+    pub fn get_best_action(&self) -> Option<Action> {
+        self.best_action
+    }
+
+    /// The last info line with a score the engine sent during the search.
+    pub fn get_last_info(&self) -> Option<&SearchInfo> {
+        self.last_info.as_ref()
+    }
+}
+
+/// A connection to an engine. The lines of the engine are read on a separate thread, so waiting
+/// for an answer can time out instead of blocking forever on an engine that hangs.
+pub struct EngineClient {
+    writer: Box<dyn Write + Send>,
+    lines: Receiver<io::Result<String>>,
+    process: Option<Child>,
+}
+
+impl EngineClient {
+    /// Starts the engine executable and connects to its standard input and output.
+    pub fn spawn<S: AsRef<OsStr>>(program: S, arguments: &[S]) -> Result<EngineClient> {
+        let mut process = Process::new(program)
+            .args(arguments)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = process
+            .stdin
+            .take()
+            .expect("The stdin of the engine is piped");
+        let stdout = process
+            .stdout
+            .take()
+            .expect("The stdout of the engine is piped");
+
+        let mut client = EngineClient::from_streams(BufReader::new(stdout), stdin);
+        client.process = Some(process);
+        Ok(client)
+    }
+
+    /// Connects to an engine through any pair of streams, for example an engine running in the
+    /// same process.
+    pub fn from_streams<R, W>(reader: R, writer: W) -> EngineClient
+    where
+        R: BufRead + Send + 'static,
+        W: Write + Send + 'static,
+    {
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        EngineClient {
+            writer: Box::new(writer),
+            lines,
+            process: None,
+        }
+    }
+
+    pub fn send(&mut self, command: &Command) -> Result<()> {
+        writeln!(self.writer, "{}", command.get_notation())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Waits for the next line of the engine. Empty lines are skipped.
+    pub fn receive(&mut self, timeout: Duration) -> Result<Response> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(remaining) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Timeout) => return Err(ClientError::Timeout),
+                Err(RecvTimeoutError::Disconnected) => return Err(ClientError::Disconnected),
+            };
+            if !line.trim().is_empty() {
+                return Response::from_notation(&line).map_err(ClientError::InvalidResponse);
+            }
+        }
+    }
+
+    /// Sends `qei` and collects the details of the engine until it answers `qeiok`.
+    pub fn handshake(&mut self, timeout: Duration) -> Result<EngineDetails> {
+        self.send(&Command::Qei)?;
+        let mut details = EngineDetails::default();
+        self.receive_until(timeout, |response| {
+            match response {
+                Response::IdName(name) => details.name = Some(name),
+                Response::IdAuthor(author) => details.author = Some(author),
+                Response::Option(option) => details.options.push(option),
+                Response::QeiOk => return true,
+                _ => (),
+            }
+            false
+        })?;
+
+        Ok(details)
+    }
+
+    /// Sends `isready` and waits for `readyok`.
+    pub fn wait_until_ready(&mut self, timeout: Duration) -> Result<()> {
+        self.send(&Command::IsReady)?;
+        self.receive_until(timeout, |response| response == Response::ReadyOk)
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<()> {
+        self.send(&Command::SetOption {
+            name: String::from(name),
+            value: String::from(value),
+        })
+    }
+
+    pub fn new_game(&mut self) -> Result<()> {
+        self.send(&Command::NewGame)
+    }
+
+    pub fn set_position(&mut self, position: &Position) -> Result<()> {
+        self.send(&Command::Position(position.clone()))
+    }
+
+    /// Starts a search and waits for the best move.
+    pub fn go(&mut self, parameters: &GoParameters, timeout: Duration) -> Result<SearchOutcome> {
+        self.send(&Command::Go(parameters.clone()))?;
+        let mut last_info = None;
+        let mut best_action = None;
+        self.receive_until(timeout, |response| match response {
+            Response::Info(info) if info.score.is_some() => {
+                last_info = Some(info);
+                false
+            }
+            Response::BestMove(action) => {
+                best_action = action;
+                true
+            }
+            _ => false,
+        })?;
+
+        Ok(SearchOutcome {
+            best_action,
+            last_info,
+        })
+    }
+
+    /// Asks the engine to stop searching, the best move still has to be received.
+    pub fn stop(&mut self) -> Result<()> {
+        self.send(&Command::Stop)
+    }
+
+    /// Receives responses until `done` returns true. Lines that are not valid responses are
+    /// skipped, since engines tend to print debug output.
+    fn receive_until(
+        &mut self,
+        timeout: Duration,
+        mut done: impl FnMut(Response) -> bool,
+    ) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.receive(remaining) {
+                Ok(response) => {
+                    if done(response) {
+                        return Ok(());
+                    }
+                }
+                Err(ClientError::InvalidResponse(_)) => (),
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+/// Asks the engine to quit, an engine process that doesn't exit within a second is killed.
+impl Drop for EngineClient {
+    fn drop(&mut self) {
+        let _ = self.send(&Command::Quit);
+        if let Some(process) = &mut self.process {
+            let deadline = Instant::now() + Duration::from_secs(1);
+            while Instant::now() < deadline {
+                if let Ok(Some(_)) = process.try_wait() {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use quoridor_core::boardstate::Boardstate;
+
+    use super::*;
+    use crate::reference::{self, ENGINE_NAME};
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Connects a client to the reference engine running on a thread.
+    fn reference_client() -> EngineClient {
+        let (engine_input, client_output) = io::pipe().unwrap();
+        let (client_input, engine_output) = io::pipe().unwrap();
+        thread::spawn(move || reference::run(BufReader::new(engine_input), engine_output));
+        EngineClient::from_streams(BufReader::new(client_input), client_output)
+    }
+
+    #[test]
+    fn handshake_with_the_reference_engine() {
+        let mut client = reference_client();
+        let details = client.handshake(TIMEOUT).unwrap();
+        assert_eq!(details.get_name(), Some(ENGINE_NAME));
+        assert_eq!(details.get_options()[0].get_name(), "Hash");
+
+        client.set_option("Hash", "1024").unwrap();
+        client.wait_until_ready(TIMEOUT).unwrap();
+    }
+
+    #[test]
+    fn reference_engine_plays_a_legal_move() {
+        let mut client = reference_client();
+        client.handshake(TIMEOUT).unwrap();
+        client.new_game().unwrap();
+
+        let position = Position::from_actions(vec![Action::from_notation("E2").unwrap()]);
+        client.set_position(&position).unwrap();
+        let outcome = client
+            .go(
+                &GoParameters {
+                    depth: Some(1),
+                    ..GoParameters::default()
+                },
+                TIMEOUT,
+            )
+            .unwrap();
+        let best_action = outcome.get_best_action().unwrap();
+        assert_eq!(outcome.get_last_info().unwrap().depth, Some(1));
+        assert!(position
+            .get_boardstate()
+            .unwrap()
+            .apply_action(best_action)
+            .is_ok());
+    }
+
+    #[test]
+    fn reference_engine_stops_an_infinite_search() {
+        let mut client = reference_client();
+        client.handshake(TIMEOUT).unwrap();
+        // White wins with the next move, the search is certain of the result right away.
+        let boardstate = Boardstate::from_position_notation("E8 A5 - 0 0 w").unwrap();
+        client
+            .set_position(&Position::build(boardstate.clone(), Vec::new()))
+            .unwrap();
+        client
+            .send(&Command::Go(GoParameters {
+                infinite: true,
+                ..GoParameters::default()
+            }))
+            .unwrap();
+
+        // The best move only comes after the stop.
+        loop {
+            match client.receive(Duration::from_millis(200)) {
+                Ok(Response::BestMove(_)) => panic!("The best move was sent before the stop"),
+                Ok(_) => (),
+                Err(ClientError::Timeout) => break,
+                Err(error) => panic!("{error}"),
+            }
+        }
+        client.stop().unwrap();
+
+        let best_action = loop {
+            if let Response::BestMove(action) = client.receive(TIMEOUT).unwrap() {
+                break action;
+            }
+        };
+        assert!(boardstate
+            .clone()
+            .apply_action(best_action.unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn invalid_commands_are_answered_with_info() {
+        let mut client = reference_client();
+        client
+            .set_position(&Position::from_actions(Vec::new()))
+            .unwrap();
+        writeln!(client.writer, "position startpos moves E2 E8 E4").unwrap();
+        client.writer.flush().unwrap();
+        let Response::Info(info) = client.receive(TIMEOUT).unwrap() else {
+            panic!("Expected info");
+        };
+        assert!(info.string.unwrap().starts_with("Illegal move E4 at ply 3"));
+    }
+
+    #[test]
+    fn missing_engine_fails_to_spawn() {
+        assert!(matches!(
+            EngineClient::spawn("./no-such-quoridor-engine", &[]),
+            Err(ClientError::Io(_))
+        ));
+    }
+}
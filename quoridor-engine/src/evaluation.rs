@@ -0,0 +1,113 @@
+use quoridor_core::boardstate::{Boardstate, Player};
+
+/// The score of winning the game on the current move, a win further away in the search scores
+/// one less for every ply it takes to get there, so the engine prefers the fastest win.
+pub const WIN_SCORE: i32 = 100_000;
+
+/// Scores above this value (or below the negative) are a forced win (or loss) found by the search.
+pub const WIN_THRESHOLD: i32 = WIN_SCORE - 1_000;
+
+/// Gives a static score of a boardstate to the search. Implement this trait to try out other
+/// heuristics with the same search, closures taking a `&Boardstate` implement it as well.
+pub trait Evaluator {
+    /// The score of the boardstate from the point of view of the active player, positive when the
+    /// active player is better off. Scores are clamped to stay below `WIN_THRESHOLD`, so they
+    /// can't be mistaken for a forced win.
+    fn evaluate(&self, boardstate: &Boardstate) -> i32;
+}
+
+impl<F> Evaluator for F
+where
+    F: Fn(&Boardstate) -> i32,
+{
+    fn evaluate(&self, boardstate: &Boardstate) -> i32 {
+        self(boardstate)
+    }
+}
+
+/// The default evaluation, mostly a race to the opposite side: how many steps the opponent needs
+/// more than the active player. Walls in hand are worth a bit less than a step, since they can
+/// still be used to slow down the opponent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShortestPathEvaluator {
+    distance_weight: i32,
+    wall_weight: i32,
+}
+
+impl ShortestPathEvaluator {
+    pub fn new(distance_weight: i32, wall_weight: i32) -> ShortestPathEvaluator {
+        ShortestPathEvaluator {
+            distance_weight,
+            wall_weight,
+        }
+    }
+}
+
+impl Default for ShortestPathEvaluator {
+    fn default() -> Self {
+        ShortestPathEvaluator::new(100, 15)
+    }
+}
+
+impl Evaluator for ShortestPathEvaluator {
+    fn evaluate(&self, boardstate: &Boardstate) -> i32 {
+        let player = boardstate.get_active_player();
+        let opponent = player.get_opponent();
+
+        let distance_difference = distance(boardstate, opponent) - distance(boardstate, player);
+        let wall_difference = i32::from(available_walls(boardstate, player))
+            - i32::from(available_walls(boardstate, opponent));
+
+        distance_difference * self.distance_weight + wall_difference * self.wall_weight
+    }
+}
+
+fn distance(boardstate: &Boardstate, player: Player) -> i32 {
+    // A legal boardstate always has a path to the goal, this is just a safe fallback.
+    boardstate
+        .get_distance_to_goal(player)
+        .map_or(i32::from(u8::MAX), i32::from)
+}
+
+fn available_walls(boardstate: &Boardstate, player: Player) -> u8 {
+    match player {
+        Player::White => boardstate.get_available_walls_white_player(),
+        Player::Black => boardstate.get_available_walls_black_player(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(boardstate: &Boardstate) -> i32 {
+        ShortestPathEvaluator::default().evaluate(boardstate)
+    }
+
+    #[test]
+    fn starting_position_is_equal() {
+        assert_eq!(evaluate(&Boardstate::new()), 0);
+    }
+
+    #[test]
+    fn closer_to_the_goal_is_better() {
+        let boardstate = Boardstate::from_position_notation("E5 E9 - 10 10 w").unwrap();
+        assert_eq!(evaluate(&boardstate), 400);
+
+        let boardstate = Boardstate::from_position_notation("E5 E9 - 10 10 b").unwrap();
+        assert_eq!(evaluate(&boardstate), -400);
+    }
+
+    #[test]
+    fn walls_in_hand_count() {
+        let boardstate = Boardstate::from_position_notation("E1 E9 - 10 8 w").unwrap();
+        assert_eq!(evaluate(&boardstate), 30);
+    }
+
+    #[test]
+    fn weights_can_be_changed() {
+        let boardstate = Boardstate::from_position_notation("E5 E9 - 10 8 w").unwrap();
+        assert_eq!(ShortestPathEvaluator::new(1, 0).evaluate(&boardstate), 4);
+        assert_eq!(ShortestPathEvaluator::new(0, 1).evaluate(&boardstate), 2);
+    }
+}
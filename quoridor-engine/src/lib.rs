@@ -0,0 +1,7 @@
+pub mod client;
+pub mod evaluation;
+pub mod mcts;
+pub mod protocol;
+pub mod reference;
+pub mod search;
+pub mod transposition;
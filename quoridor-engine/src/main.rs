@@ -0,0 +1,7 @@
+use std::io;
+
+/// Runs the reference engine on the standard input and output, so interfaces and match runners
+/// can talk to it over the QEI protocol.
+fn main() -> io::Result<()> {
+    quoridor_engine::reference::run(io::stdin().lock(), io::stdout())
+}
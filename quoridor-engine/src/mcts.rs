@@ -0,0 +1,359 @@
+use std::cmp::{Ordering, Reverse};
+use std::time::{Duration, Instant};
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::{Boardstate, Player, PlayoutPolicy};
+use quoridor_core::gamestate::GameStatus;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// How many iterations the search runs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MctsBudget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+/// The settings of the Monte Carlo tree search. The seed makes searches reproducible, two
+/// searches with the same config on the same boardstate visit the same nodes.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MctsConfig {
+    /// The exploration constant of UCT, higher values spread the visits over more actions.
+    pub exploration: f64,
+    pub policy: PlayoutPolicy,
+    pub budget: MctsBudget,
+    /// A playout that takes longer than this is stopped, the player closest to the goal is
+    /// counted as the winner.
+    pub max_playout_plies: u16,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f64::consts::SQRT_2,
+            policy: PlayoutPolicy::ShortestPathBiased { probability: 0.7 },
+            budget: MctsBudget::Iterations(1_000),
+            max_playout_plies: 200,
+            seed: 0,
+        }
+    }
+}
+
+/// The outcome of a Monte Carlo tree search, the best action is the action that was visited the
+/// most.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MctsResult {
+    best_action: Action,
+    visits: Vec<(Action, u32)>,
+    win_rate: f64,
+    iterations: u32,
+}
+
+impl MctsResult {
+    pub fn get_best_action(&self) -> Action {
+        self.best_action
+    }
+
+    /// The number of visits of every action from the root, sorted from most to least visited.
+    pub fn get_visits(&self) -> &Vec<(Action, u32)> {
+        &self.visits
+    }
+
+    pub fn get_visit_count(&self, action: Action) -> u32 {
+        self.visits
+            .iter()
+            .find(|(visited_action, _)| *visited_action == action)
+            .map_or(0, |(_, visits)| *visits)
+    }
+
+    /// The fraction of the playouts through the best action that were won by the active player.
+    pub fn get_win_rate(&self) -> f64 {
+        self.win_rate
+    }
+
+    pub fn get_iterations(&self) -> u32 {
+        self.iterations
+    }
+}
+
+struct Node {
+    action: Option<Action>,
+    /// The player that played the action leading to this node.
+    player: Player,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried_actions: Vec<Action>,
+    winner: Option<Player>,
+    visits: u32,
+    wins: f64,
+}
+
+/// A Monte Carlo tree search using UCT to select the nodes. Every iteration copies the root
+/// boardstate, walks down the tree applying the actions of the nodes, adds one new node and plays
+/// the rest of the game out according to the playout policy.
+pub struct Mcts {
+    config: MctsConfig,
+    rng: StdRng,
+    nodes: Vec<Node>,
+}
+
+impl Mcts {
+    pub fn new(config: MctsConfig) -> Mcts {
+        Mcts {
+            config,
+            rng: StdRng::seed_from_u64(config.seed),
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Searches the boardstate for the best action for the active player. Returns None when the
+    /// active player has no legal actions.
+    pub fn search(&mut self, boardstate: &Boardstate) -> Option<MctsResult> {
+        self.rng = StdRng::seed_from_u64(self.config.seed);
+        self.nodes = vec![Node {
+            action: None,
+            player: boardstate.get_active_player().get_opponent(),
+            parent: None,
+            children: Vec::new(),
+            untried_actions: self.shuffled_actions(boardstate),
+            winner: None,
+            visits: 0,
+            wins: 0.0,
+        }];
+        if self.nodes[0].untried_actions.is_empty() {
+            return None;
+        }
+
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            match self.config.budget {
+                MctsBudget::Iterations(budget) if iterations >= budget.max(1) => break,
+                MctsBudget::Time(time) if iterations > 0 && start.elapsed() >= time => break,
+                _ => (),
+            }
+            self.iterate(boardstate);
+            iterations += 1;
+        }
+
+        let mut visits: Vec<(Action, u32, f64)> = self.nodes[0]
+            .children
+            .iter()
+            .map(|child| {
+                let node = &self.nodes[*child];
+                let action = node.action.expect("Only the root node has no action");
+                (action, node.visits, node.wins / f64::from(node.visits))
+            })
+            .collect();
+        visits.sort_by_key(|(_, visits, _)| Reverse(*visits));
+        let (best_action, _, win_rate) = visits[0];
+
+        Some(MctsResult {
+            best_action,
+            visits: visits
+                .into_iter()
+                .map(|(action, visits, _)| (action, visits))
+                .collect(),
+            win_rate,
+            iterations,
+        })
+    }
+
+    fn iterate(&mut self, root: &Boardstate) {
+        let mut boardstate = root.clone();
+        let mut node = 0;
+
+        // Selection, walk down the fully expanded nodes.
+        while self.nodes[node].winner.is_none()
+            && self.nodes[node].untried_actions.is_empty()
+            && !self.nodes[node].children.is_empty()
+        {
+            node = self.select_child(node);
+            let action = self.nodes[node]
+                .action
+                .expect("Only the root has no action");
+            boardstate
+                .apply_action(action)
+                .expect("The actions in the tree are legal");
+        }
+
+        // Expansion, add one of the actions that are not in the tree yet.
+        if self.nodes[node].winner.is_none() {
+            if let Some(action) = self.nodes[node].untried_actions.pop() {
+                let player = boardstate.get_active_player();
+                let status = boardstate
+                    .apply_action(action)
+                    .expect("The untried actions are legal");
+                let winner = match status {
+                    GameStatus::Finished { won_by, .. } => Some(won_by),
+                    GameStatus::InProgress => None,
+                };
+                let untried_actions = match winner {
+                    Some(_) => Vec::new(),
+                    None => self.shuffled_actions(&boardstate),
+                };
+                self.nodes.push(Node {
+                    action: Some(action),
+                    player,
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried_actions,
+                    winner,
+                    visits: 0,
+                    wins: 0.0,
+                });
+                let child = self.nodes.len() - 1;
+                self.nodes[node].children.push(child);
+                node = child;
+            }
+        }
+
+        // Simulation, play the game out from the new node.
+        let winner = match self.nodes[node].winner {
+            Some(winner) => Some(winner),
+            None => self.playout(&mut boardstate),
+        };
+
+        // Backpropagation, a node scores for the player that played its action.
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.wins += match winner {
+                Some(winner) if winner == node.player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+            current = node.parent;
+        }
+    }
+
+    fn select_child(&self, node: usize) -> usize {
+        let log_visits = f64::from(self.nodes[node].visits).ln();
+        let uct = |child: usize| {
+            let child = &self.nodes[child];
+            let visits = f64::from(child.visits);
+            child.wins / visits + self.config.exploration * (log_visits / visits).sqrt()
+        };
+
+        *self.nodes[node]
+            .children
+            .iter()
+            .max_by(|a, b| uct(**a).total_cmp(&uct(**b)))
+            .expect("Only called on nodes with children")
+    }
+
+    /// Plays the game out with the playout policy, returns the winner or None when the playout
+    /// took too long and both pawns are as far from the goal.
+    fn playout(&mut self, boardstate: &mut Boardstate) -> Option<Player> {
+        let status = boardstate.playout_with_limit(
+            &mut self.rng,
+            self.config.policy,
+            usize::from(self.config.max_playout_plies),
+        );
+        if let GameStatus::Finished { won_by, .. } = status {
+            return Some(won_by);
+        }
+
+        let white = boardstate.get_distance_to_goal(Player::White);
+        let black = boardstate.get_distance_to_goal(Player::Black);
+        match white.cmp(&black) {
+            Ordering::Less => Some(Player::White),
+            Ordering::Greater => Some(Player::Black),
+            Ordering::Equal => None,
+        }
+    }
+
+    fn shuffled_actions(&mut self, boardstate: &Boardstate) -> Vec<Action> {
+        let mut actions = boardstate.get_legal_actions().get_actions().clone();
+        actions.shuffle(&mut self.rng);
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quoridor_core::locations::PawnLocation;
+
+    use super::*;
+
+    fn config(iterations: u32) -> MctsConfig {
+        MctsConfig {
+            budget: MctsBudget::Iterations(iterations),
+            max_playout_plies: 10,
+            ..MctsConfig::default()
+        }
+    }
+
+    #[test]
+    fn visits_add_up_to_the_iterations() {
+        let result = Mcts::new(config(40)).search(&Boardstate::new()).unwrap();
+        assert_eq!(result.get_iterations(), 40);
+        let visits: u32 = result.get_visits().iter().map(|(_, visits)| visits).sum();
+        assert_eq!(visits, 40);
+        assert_eq!(
+            result.get_visit_count(result.get_best_action()),
+            result.get_visits()[0].1
+        );
+    }
+
+    #[test]
+    fn takes_the_winning_move() {
+        let boardstate = Boardstate::from_position_notation("E8 A5 - 0 0 w").unwrap();
+        let result = Mcts::new(config(300)).search(&boardstate).unwrap();
+        assert_eq!(
+            result.get_best_action(),
+            Action::Pawn(PawnLocation::build(76).unwrap())
+        );
+        assert_eq!(result.get_win_rate(), 1.0);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_result() {
+        let boardstate = Boardstate::from_position_notation("E3 E7 - 10 10 w").unwrap();
+        let first = Mcts::new(config(20)).search(&boardstate).unwrap();
+        let second = Mcts::new(config(20)).search(&boardstate).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn playout_returns_the_winner() {
+        let mut mcts = Mcts::new(MctsConfig {
+            policy: PlayoutPolicy::ShortestPathBiased { probability: 1.0 },
+            ..config(1)
+        });
+        let mut boardstate = Boardstate::from_position_notation("E8 A5 - 0 0 w").unwrap();
+        assert_eq!(mcts.playout(&mut boardstate), Some(Player::White));
+        assert_eq!(
+            boardstate.get_position_pawn(Player::White),
+            PawnLocation::build(76).unwrap()
+        );
+    }
+
+    #[test]
+    fn unfinished_playout_is_won_by_the_closest_pawn() {
+        let mut mcts = Mcts::new(MctsConfig {
+            max_playout_plies: 0,
+            ..config(1)
+        });
+        let mut boardstate = Boardstate::from_position_notation("E8 E5 - 10 10 w").unwrap();
+        assert_eq!(mcts.playout(&mut boardstate), Some(Player::White));
+        let mut boardstate = Boardstate::from_position_notation("E4 E6 - 10 10 w").unwrap();
+        assert_eq!(mcts.playout(&mut boardstate), None);
+    }
+
+    #[test]
+    fn search_stops_at_the_time_limit() {
+        let start = Instant::now();
+        let result = Mcts::new(MctsConfig {
+            budget: MctsBudget::Time(Duration::from_millis(50)),
+            ..config(0)
+        })
+        .search(&Boardstate::new())
+        .unwrap();
+        assert!(result.get_iterations() >= 1);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,654 @@
+//! The Quoridor Engine Interface (QEI), a line based text protocol between a graphical interface
+//! and an engine, modelled on the UCI protocol of chess engines.
+//!
+//! The interface writes commands to the standard input of the engine, the engine answers on its
+//! standard output. Every command and response is a single line:
+//!
+//! ```text
+//! > qei
+//! < id name quoridor-engine 0.1.0
+//! < id author Quoridor developers
+//! < option name Hash type spin default 262144 min 1 max 16777216
+//! < qeiok
+//! > isready
+//! < readyok
+//! > newgame
+//! > position startpos moves E2 E8
+//! > go wtime 60000 btime 60000 winc 1000 binc 1000
+//! < info depth 1 score 15 nodes 132 time 3 pv D7h
+//! < bestmove D7h
+//! ```
+//!
+//! A position is either `startpos` or `notation` followed by the six fields of the position
+//! notation of `Boardstate`, optionally followed by `moves` and the actions played from there.
+//! Actions are written with `Action::get_notation` and times are in milliseconds.
+
+use std::time::Duration;
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::Boardstate;
+use quoridor_core::error::{QuoridorError, Result};
+
+/// The first command sent to the engine, the engine has to answer with `qeiok`.
+pub const HANDSHAKE: &str = "qei";
+
+/// A command sent by the interface to the engine.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Asks the engine to identify itself and its options, answered with `qeiok`.
+    Qei,
+    /// Answered with `readyok` once the engine has handled all previous commands.
+    IsReady,
+    SetOption {
+        name: String,
+        value: String,
+    },
+    /// The next position is from another game, the engine can clear its state.
+    NewGame,
+    Position(Position),
+    /// Starts searching the last position, the engine answers with `bestmove` when it is done.
+    Go(GoParameters),
+    /// Stops the search as soon as possible, the engine still answers with `bestmove`.
+    Stop,
+    Quit,
+}
+
+impl Command {
+    pub fn from_notation(line: &str) -> Result<Command> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((keyword, arguments)) = tokens.split_first() else {
+            return Err(invalid_notation(line, "The command is empty"));
+        };
+
+        match *keyword {
+            HANDSHAKE => Ok(Command::Qei),
+            "isready" => Ok(Command::IsReady),
+            "setoption" => {
+                let (name, value) = name_value_from_tokens(arguments)
+                    .ok_or_else(|| invalid_notation(line, "Expected setoption name .. value .."))?;
+                Ok(Command::SetOption { name, value })
+            }
+            "newgame" => Ok(Command::NewGame),
+            "position" => Ok(Command::Position(Position::from_tokens(line, arguments)?)),
+            "go" => Ok(Command::Go(GoParameters::from_tokens(line, arguments)?)),
+            "stop" => Ok(Command::Stop),
+            "quit" => Ok(Command::Quit),
+            _ => Err(invalid_notation(line, "Unknown command")),
+        }
+    }
+
+    pub fn get_notation(&self) -> String {
+        match self {
+            Command::Qei => String::from(HANDSHAKE),
+            Command::IsReady => String::from("isready"),
+            Command::SetOption { name, value } => format!("setoption name {name} value {value}"),
+            Command::NewGame => String::from("newgame"),
+            Command::Position(position) => format!("position {}", position.get_notation()),
+            Command::Go(parameters) => format!("go{}", parameters.get_notation()),
+            Command::Stop => String::from("stop"),
+            Command::Quit => String::from("quit"),
+        }
+    }
+}
+
+/// The position to search, a start position with the actions played from there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    start: Boardstate,
+    actions: Vec<Action>,
+}
+
+impl Position {
+    pub fn build(start: Boardstate, actions: Vec<Action>) -> Position {
+        Position { start, actions }
+    }
+
+    /// A position from the start of a game.
+    pub fn from_actions(actions: Vec<Action>) -> Position {
+        Position::build(Boardstate::new(), actions)
+    }
+
+    pub fn get_start(&self) -> &Boardstate {
+        &self.start
+    }
+
+    pub fn get_actions(&self) -> &Vec<Action> {
+        &self.actions
+    }
+
+    /// Plays the actions from the start position, fails when one of them is illegal.
+    pub fn get_boardstate(&self) -> Result<Boardstate> {
+        let mut boardstate = self.start.clone();
+        for (ply, action) in self.actions.iter().enumerate() {
+            boardstate
+                .apply_action(*action)
+                .map_err(|error| QuoridorError::IllegalRecordMove {
+                    ply: ply + 1,
+                    notation: action.get_notation(),
+                    reason: Box::new(error),
+                })?;
+        }
+
+        Ok(boardstate)
+    }
+
+    fn get_notation(&self) -> String {
+        let mut notation = if self.start == Boardstate::new() {
+            String::from("startpos")
+        } else {
+            format!("notation {}", self.start.get_position_notation())
+        };
+        if !self.actions.is_empty() {
+            notation.push_str(" moves");
+            for action in &self.actions {
+                notation.push(' ');
+                notation.push_str(&action.get_notation());
+            }
+        }
+        notation
+    }
+
+    fn from_tokens(line: &str, tokens: &[&str]) -> Result<Position> {
+        let (start, rest) = match tokens.split_first() {
+            Some((&"startpos", rest)) => (Boardstate::new(), rest),
+            Some((&"notation", rest)) if rest.len() >= 6 => (
+                Boardstate::from_position_notation(&rest[..6].join(" "))?,
+                &rest[6..],
+            ),
+            _ => {
+                return Err(invalid_notation(
+                    line,
+                    "Expected startpos or notation with the six fields of a position",
+                ))
+            }
+        };
+
+        let actions = match rest.split_first() {
+            None => Vec::new(),
+            Some((&"moves", actions)) => actions
+                .iter()
+                .map(|action| Action::from_notation(action))
+                .collect::<Result<Vec<Action>>>()?,
+            Some(_) => return Err(invalid_notation(line, "Expected moves after the position")),
+        };
+
+        Ok(Position::build(start, actions))
+    }
+}
+
+/// The limits of a search. Without any limit the engine searches until it receives `stop`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoParameters {
+    pub white_time: Option<Duration>,
+    pub black_time: Option<Duration>,
+    pub white_increment: Option<Duration>,
+    pub black_increment: Option<Duration>,
+    /// Search exactly this long.
+    pub move_time: Option<Duration>,
+    pub depth: Option<u8>,
+    pub infinite: bool,
+}
+
+impl GoParameters {
+    fn get_notation(&self) -> String {
+        let mut notation = String::new();
+        for (name, time) in [
+            ("wtime", self.white_time),
+            ("btime", self.black_time),
+            ("winc", self.white_increment),
+            ("binc", self.black_increment),
+            ("movetime", self.move_time),
+        ] {
+            if let Some(time) = time {
+                notation.push_str(&format!(" {name} {}", time.as_millis()));
+            }
+        }
+        if let Some(depth) = self.depth {
+            notation.push_str(&format!(" depth {depth}"));
+        }
+        if self.infinite {
+            notation.push_str(" infinite");
+        }
+        notation
+    }
+
+    fn from_tokens(line: &str, tokens: &[&str]) -> Result<GoParameters> {
+        let mut parameters = GoParameters::default();
+        let mut tokens = tokens.iter();
+        while let Some(token) = tokens.next() {
+            if *token == "infinite" {
+                parameters.infinite = true;
+                continue;
+            }
+            let value = tokens
+                .next()
+                .ok_or_else(|| invalid_notation(line, &format!("Missing value for {token}")))?;
+            match *token {
+                "wtime" => parameters.white_time = Some(millis_from_token(line, value)?),
+                "btime" => parameters.black_time = Some(millis_from_token(line, value)?),
+                "winc" => parameters.white_increment = Some(millis_from_token(line, value)?),
+                "binc" => parameters.black_increment = Some(millis_from_token(line, value)?),
+                "movetime" => parameters.move_time = Some(millis_from_token(line, value)?),
+                "depth" => parameters.depth = Some(number_from_token(line, value)?),
+                _ => {
+                    return Err(invalid_notation(
+                        line,
+                        &format!("Unknown go parameter {token}"),
+                    ))
+                }
+            }
+        }
+
+        Ok(parameters)
+    }
+}
+
+/// A line sent by the engine to the interface.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Response {
+    IdName(String),
+    IdAuthor(String),
+    Option(EngineOption),
+    QeiOk,
+    ReadyOk,
+    Info(SearchInfo),
+    /// The result of a search, None when the engine has no legal action to play.
+    BestMove(Option<Action>),
+}
+
+impl Response {
+    pub fn from_notation(line: &str) -> Result<Response> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some((keyword, arguments)) = tokens.split_first() else {
+            return Err(invalid_notation(line, "The response is empty"));
+        };
+
+        match (*keyword, arguments) {
+            ("id", [kind, value @ ..]) if *kind == "name" => Ok(Response::IdName(value.join(" "))),
+            ("id", [kind, value @ ..]) if *kind == "author" => {
+                Ok(Response::IdAuthor(value.join(" ")))
+            }
+            ("option", _) => Ok(Response::Option(EngineOption::from_tokens(
+                line, arguments,
+            )?)),
+            ("qeiok", []) => Ok(Response::QeiOk),
+            ("readyok", []) => Ok(Response::ReadyOk),
+            ("info", _) => Ok(Response::Info(SearchInfo::from_tokens(line, arguments)?)),
+            ("bestmove", ["none"]) => Ok(Response::BestMove(None)),
+            ("bestmove", [action]) => Ok(Response::BestMove(Some(Action::from_notation(action)?))),
+            _ => Err(invalid_notation(line, "Unknown response")),
+        }
+    }
+
+    pub fn get_notation(&self) -> String {
+        match self {
+            Response::IdName(name) => format!("id name {name}"),
+            Response::IdAuthor(author) => format!("id author {author}"),
+            Response::Option(option) => format!("option {}", option.get_notation()),
+            Response::QeiOk => String::from("qeiok"),
+            Response::ReadyOk => String::from("readyok"),
+            Response::Info(info) => format!("info{}", info.get_notation()),
+            Response::BestMove(Some(action)) => format!("bestmove {}", action.get_notation()),
+            Response::BestMove(None) => String::from("bestmove none"),
+        }
+    }
+}
+
+/// An option the engine supports, announced in the answer to `qei`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EngineOption {
+    name: String,
+    kind: OptionKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionKind {
+    Spin { default: i64, min: i64, max: i64 },
+    Check { default: bool },
+    String { default: String },
+}
+
+impl EngineOption {
+    pub fn new(name: &str, kind: OptionKind) -> EngineOption {
+        EngineOption {
+            name: String::from(name),
+            kind,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_kind(&self) -> &OptionKind {
+        &self.kind
+    }
+
+    fn get_notation(&self) -> String {
+        let kind = match &self.kind {
+            OptionKind::Spin { default, min, max } => {
+                format!("type spin default {default} min {min} max {max}")
+            }
+            OptionKind::Check { default } => format!("type check default {default}"),
+            OptionKind::String { default } => format!("type string default {default}"),
+        };
+        format!("name {} {}", self.name, kind)
+    }
+
+    fn from_tokens(line: &str, tokens: &[&str]) -> Result<EngineOption> {
+        let type_index = tokens
+            .iter()
+            .position(|token| *token == "type")
+            .ok_or_else(|| invalid_notation(line, "An option needs a type"))?;
+        let name = match tokens[..type_index].split_first() {
+            Some((&"name", name)) if !name.is_empty() => name.join(" "),
+            _ => return Err(invalid_notation(line, "An option needs a name")),
+        };
+        let field = |field: &str| -> Option<String> {
+            let index = tokens.iter().position(|token| *token == field)?;
+            let end = tokens[index + 1..]
+                .iter()
+                .position(|token| matches!(*token, "default" | "min" | "max"))
+                .map_or(tokens.len(), |end| index + 1 + end);
+            Some(tokens[index + 1..end].join(" "))
+        };
+        let spin_field = |name: &str| -> Result<i64> {
+            number_from_token(line, &field(name).unwrap_or_default())
+        };
+
+        let kind = match tokens.get(type_index + 1) {
+            Some(&"spin") => OptionKind::Spin {
+                default: spin_field("default")?,
+                min: spin_field("min")?,
+                max: spin_field("max")?,
+            },
+            Some(&"check") => OptionKind::Check {
+                default: field("default").as_deref() == Some("true"),
+            },
+            Some(&"string") => OptionKind::String {
+                default: field("default").unwrap_or_default(),
+            },
+            _ => return Err(invalid_notation(line, "Unknown option type")),
+        };
+
+        Ok(EngineOption { name, kind })
+    }
+}
+
+/// Progress of the search, every field is optional. The score is from the point of view of the
+/// player to move.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchInfo {
+    pub depth: Option<u8>,
+    pub score: Option<i32>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    pub principal_variation: Vec<Action>,
+    /// Free text, for example to explain why a command was ignored. Always the last field.
+    pub string: Option<String>,
+}
+
+impl SearchInfo {
+    fn get_notation(&self) -> String {
+        let mut notation = String::new();
+        if let Some(depth) = self.depth {
+            notation.push_str(&format!(" depth {depth}"));
+        }
+        if let Some(score) = self.score {
+            notation.push_str(&format!(" score {score}"));
+        }
+        if let Some(nodes) = self.nodes {
+            notation.push_str(&format!(" nodes {nodes}"));
+        }
+        if let Some(time) = self.time {
+            notation.push_str(&format!(" time {}", time.as_millis()));
+        }
+        if !self.principal_variation.is_empty() {
+            notation.push_str(" pv");
+            for action in &self.principal_variation {
+                notation.push(' ');
+                notation.push_str(&action.get_notation());
+            }
+        }
+        if let Some(string) = &self.string {
+            notation.push_str(&format!(" string {string}"));
+        }
+        notation
+    }
+
+    fn from_tokens(line: &str, tokens: &[&str]) -> Result<SearchInfo> {
+        let mut info = SearchInfo::default();
+        let mut index = 0;
+        while index < tokens.len() {
+            let value = tokens.get(index + 1).copied().unwrap_or_default();
+            match tokens[index] {
+                "depth" => info.depth = Some(number_from_token(line, value)?),
+                "score" => info.score = Some(number_from_token(line, value)?),
+                "nodes" => info.nodes = Some(number_from_token(line, value)?),
+                "time" => info.time = Some(millis_from_token(line, value)?),
+                "pv" => {
+                    index += 1;
+                    while let Some(Ok(action)) =
+                        tokens.get(index).map(|token| Action::from_notation(token))
+                    {
+                        info.principal_variation.push(action);
+                        index += 1;
+                    }
+                    continue;
+                }
+                "string" => {
+                    info.string = Some(tokens[index + 1..].join(" "));
+                    break;
+                }
+                // Unknown fields are skipped with their value, so engines can send more.
+                _ => (),
+            }
+            index += 2;
+        }
+
+        Ok(info)
+    }
+}
+
+fn invalid_notation(line: &str, reason: &str) -> QuoridorError {
+    QuoridorError::InvalidNotation {
+        notation: String::from(line),
+        reason: String::from(reason),
+    }
+}
+
+/// Splits `name <name> value <value>`, both the name and the value can contain spaces.
+fn name_value_from_tokens(tokens: &[&str]) -> Option<(String, String)> {
+    let (first, rest) = tokens.split_first()?;
+    if *first != "name" {
+        return None;
+    }
+    let value_index = rest.iter().position(|token| *token == "value")?;
+    let name = rest[..value_index].join(" ");
+    if name.is_empty() {
+        return None;
+    }
+
+    Some((name, rest[value_index + 1..].join(" ")))
+}
+
+fn number_from_token<T: std::str::FromStr>(line: &str, token: &str) -> Result<T> {
+    token
+        .parse()
+        .map_err(|_| invalid_notation(line, &format!("Expected a number, but got {token}")))
+}
+
+fn millis_from_token(line: &str, token: &str) -> Result<Duration> {
+    Ok(Duration::from_millis(number_from_token(line, token)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use quoridor_core::locations::{PawnLocation, WallLocation, WallOrientation};
+
+    use super::*;
+
+    #[test]
+    fn commands_round_trip() {
+        let commands = [
+            Command::Qei,
+            Command::IsReady,
+            Command::SetOption {
+                name: String::from("Hash"),
+                value: String::from("1024"),
+            },
+            Command::NewGame,
+            Command::Position(Position::from_actions(Vec::new())),
+            Command::Position(Position::from_actions(vec![
+                Action::Pawn(PawnLocation::build(13).unwrap()),
+                Action::Wall(WallLocation::build(30, WallOrientation::Vertical).unwrap()),
+            ])),
+            Command::Position(Position::build(
+                Boardstate::from_position_notation("E5 E6 C3h 9 10 b").unwrap(),
+                vec![Action::Pawn(PawnLocation::build(58).unwrap())],
+            )),
+            Command::Go(GoParameters {
+                white_time: Some(Duration::from_millis(60_000)),
+                black_time: Some(Duration::from_millis(59_500)),
+                white_increment: Some(Duration::from_millis(1_000)),
+                black_increment: Some(Duration::from_millis(1_000)),
+                ..GoParameters::default()
+            }),
+            Command::Go(GoParameters {
+                depth: Some(4),
+                infinite: true,
+                ..GoParameters::default()
+            }),
+            Command::Go(GoParameters::default()),
+            Command::Stop,
+            Command::Quit,
+        ];
+        for command in commands {
+            assert_eq!(
+                Command::from_notation(&command.get_notation()).unwrap(),
+                command,
+                "{}",
+                command.get_notation()
+            );
+        }
+    }
+
+    #[test]
+    fn command_notation() {
+        let command = Command::from_notation("position startpos moves E2 E8 D7h").unwrap();
+        let Command::Position(position) = command else {
+            panic!("Expected a position");
+        };
+        assert_eq!(position.get_actions().len(), 3);
+        assert_eq!(
+            position
+                .get_boardstate()
+                .unwrap()
+                .get_available_walls_white_player(),
+            9
+        );
+        assert_eq!(
+            Command::from_notation("setoption name Move Overhead value 50 ms").unwrap(),
+            Command::SetOption {
+                name: String::from("Move Overhead"),
+                value: String::from("50 ms"),
+            }
+        );
+    }
+
+    #[test]
+    fn illegal_position_moves_are_reported() {
+        let Command::Position(position) =
+            Command::from_notation("position startpos moves E2 E8 E4").unwrap()
+        else {
+            panic!("Expected a position");
+        };
+        assert!(position
+            .get_boardstate()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Illegal move E4 at ply 3"));
+    }
+
+    #[test]
+    fn invalid_commands() {
+        for line in [
+            "",
+            "go fast",
+            "go wtime",
+            "go depth many",
+            "position",
+            "position startpos E2",
+            "position notation E1 E9 - 10 10",
+            "position startpos moves Z9",
+            "setoption Hash 10",
+            "dance",
+        ] {
+            assert!(Command::from_notation(line).is_err(), "{line}");
+        }
+    }
+
+    #[test]
+    fn responses_round_trip() {
+        let responses = [
+            Response::IdName(String::from("quoridor-engine 0.1.0")),
+            Response::IdAuthor(String::from("Quoridor developers")),
+            Response::Option(EngineOption::new(
+                "Hash",
+                OptionKind::Spin {
+                    default: 16,
+                    min: 1,
+                    max: 1024,
+                },
+            )),
+            Response::Option(EngineOption::new(
+                "Use Walls",
+                OptionKind::Check { default: true },
+            )),
+            Response::Option(EngineOption::new(
+                "Book File",
+                OptionKind::String {
+                    default: String::from("book one.bin"),
+                },
+            )),
+            Response::QeiOk,
+            Response::ReadyOk,
+            Response::Info(SearchInfo {
+                depth: Some(3),
+                score: Some(-120),
+                nodes: Some(4_000),
+                time: Some(Duration::from_millis(35)),
+                principal_variation: vec![
+                    Action::Pawn(PawnLocation::build(13).unwrap()),
+                    Action::Wall(WallLocation::build(30, WallOrientation::Horizontal).unwrap()),
+                ],
+                string: None,
+            }),
+            Response::Info(SearchInfo {
+                string: Some(String::from("Unknown option Ponder")),
+                ..SearchInfo::default()
+            }),
+            Response::BestMove(Some(Action::Pawn(PawnLocation::build(13).unwrap()))),
+            Response::BestMove(None),
+        ];
+        for response in responses {
+            assert_eq!(
+                Response::from_notation(&response.get_notation()).unwrap(),
+                response,
+                "{}",
+                response.get_notation()
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_info_fields_are_skipped() {
+        let Response::Info(info) =
+            Response::from_notation("info depth 2 seldepth 5 hashfull 10 pv E2").unwrap()
+        else {
+            panic!("Expected info");
+        };
+        assert_eq!(info.depth, Some(2));
+        assert_eq!(info.principal_variation.len(), 1);
+    }
+}
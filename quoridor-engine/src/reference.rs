@@ -0,0 +1,226 @@
+//! The reference engine, the alpha-beta search of this crate behind the QEI protocol. The
+//! `quoridor-engine` binary runs it on the standard input and output.
+
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use quoridor_core::boardstate::{Boardstate, Player};
+
+use crate::protocol::{Command, EngineOption, GoParameters, OptionKind, Response, SearchInfo};
+use crate::search::{Engine, SearchLimit};
+use crate::transposition::DEFAULT_TABLE_ENTRIES;
+
+pub const ENGINE_NAME: &str = concat!("quoridor-engine ", env!("CARGO_PKG_VERSION"));
+pub const ENGINE_AUTHOR: &str = "Quoridor developers";
+
+/// The largest transposition table the Hash option allows.
+const MAX_TABLE_ENTRIES: i64 = 1 << 24;
+
+/// The part of the remaining time that is spent on a single move.
+const MOVES_TO_GO: u32 = 20;
+
+type Output<W> = Arc<Mutex<W>>;
+
+/// Reads commands from the input until `quit` or the end of the input, and writes the responses
+/// to the output. Searches run on a separate thread, so `stop` and `isready` are answered while
+/// searching.
+pub fn run<R, W>(input: R, output: W) -> io::Result<()>
+where
+    R: BufRead,
+    W: Write + Send + 'static,
+{
+    let output = Arc::new(Mutex::new(output));
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let mut engine = Engine::new();
+    engine.set_stop_flag(Arc::clone(&stop_flag));
+
+    let mut state = ReferenceEngine {
+        output,
+        stop_flag,
+        engine: Some(engine),
+        search: None,
+        boardstate: Boardstate::new(),
+    };
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Command::from_notation(&line) {
+            Ok(Command::Quit) => break,
+            Ok(command) => state.handle(command)?,
+            Err(error) => state.send_string(&error.to_string())?,
+        }
+    }
+
+    Ok(())
+}
+
+struct ReferenceEngine<W: Write + Send + 'static> {
+    output: Output<W>,
+    stop_flag: Arc<AtomicBool>,
+    /// The engine is moved to the search thread while it is searching.
+    engine: Option<Engine>,
+    search: Option<JoinHandle<Engine>>,
+    boardstate: Boardstate,
+}
+
+impl<W: Write + Send + 'static> ReferenceEngine<W> {
+    fn handle(&mut self, command: Command) -> io::Result<()> {
+        match command {
+            Command::Qei => {
+                send(&self.output, &Response::IdName(String::from(ENGINE_NAME)))?;
+                send(
+                    &self.output,
+                    &Response::IdAuthor(String::from(ENGINE_AUTHOR)),
+                )?;
+                send(
+                    &self.output,
+                    &Response::Option(EngineOption::new(
+                        "Hash",
+                        OptionKind::Spin {
+                            default: DEFAULT_TABLE_ENTRIES as i64,
+                            min: 1,
+                            max: MAX_TABLE_ENTRIES,
+                        },
+                    )),
+                )?;
+                send(&self.output, &Response::QeiOk)
+            }
+            Command::IsReady => send(&self.output, &Response::ReadyOk),
+            Command::SetOption { name, value } => {
+                if !name.eq_ignore_ascii_case("Hash") {
+                    return self.send_string(&format!("Unknown option {name}"));
+                }
+                match value.parse::<i64>() {
+                    Ok(entries) if (1..=MAX_TABLE_ENTRIES).contains(&entries) => {
+                        self.get_engine().set_table_size(entries as usize);
+                        Ok(())
+                    }
+                    _ => self.send_string(&format!("Invalid value {value} for option Hash")),
+                }
+            }
+            Command::NewGame => {
+                self.get_engine().clear_table();
+                self.boardstate = Boardstate::new();
+                Ok(())
+            }
+            Command::Position(position) => match position.get_boardstate() {
+                Ok(boardstate) => {
+                    self.boardstate = boardstate;
+                    Ok(())
+                }
+                Err(error) => self.send_string(&error.to_string()),
+            },
+            Command::Go(parameters) => {
+                self.start_search(&parameters);
+                Ok(())
+            }
+            Command::Stop => {
+                self.stop_search();
+                Ok(())
+            }
+            Command::Quit => Ok(()),
+        }
+    }
+
+    fn start_search(&mut self, parameters: &GoParameters) {
+        self.stop_search();
+        let mut engine = self
+            .engine
+            .take()
+            .expect("The engine is back from the search");
+        self.stop_flag.store(false, Ordering::Relaxed);
+
+        let limit = search_limit(parameters, self.boardstate.get_active_player());
+        let boardstate = self.boardstate.clone();
+        let output = Arc::clone(&self.output);
+        self.search = Some(thread::spawn(move || {
+            let start = Instant::now();
+            let result = engine.search_with_info(&boardstate, limit, |result| {
+                let info = SearchInfo {
+                    depth: Some(result.get_depth()),
+                    score: Some(result.get_score()),
+                    nodes: Some(result.get_nodes()),
+                    time: Some(start.elapsed()),
+                    principal_variation: result.get_principal_variation().clone(),
+                    string: None,
+                };
+                // A closed output only means nobody is listening anymore.
+                let _ = send(&output, &Response::Info(info));
+            });
+            let _ = send(
+                &output,
+                &Response::BestMove(result.map(|result| result.get_best_action())),
+            );
+            engine
+        }));
+    }
+
+    /// Stops a running search and waits for it to send its best move.
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop_flag.store(true, Ordering::Relaxed);
+            let engine = search.join().expect("The search thread doesn't panic");
+            self.engine = Some(engine);
+        }
+    }
+
+    /// Takes the engine back from a search that is still running, a new command for the engine
+    /// ends the search.
+    fn get_engine(&mut self) -> &mut Engine {
+        self.stop_search();
+        self.engine
+            .as_mut()
+            .expect("The engine is back from the search")
+    }
+
+    fn send_string(&self, string: &str) -> io::Result<()> {
+        send(
+            &self.output,
+            &Response::Info(SearchInfo {
+                string: Some(String::from(string)),
+                ..SearchInfo::default()
+            }),
+        )
+    }
+}
+
+impl<W: Write + Send + 'static> Drop for ReferenceEngine<W> {
+    fn drop(&mut self) {
+        self.stop_search();
+    }
+}
+
+fn send<W: Write>(output: &Output<W>, response: &Response) -> io::Result<()> {
+    let mut output = output.lock().expect("Writing a response doesn't panic");
+    writeln!(output, "{}", response.get_notation())?;
+    output.flush()
+}
+
+/// Picks how long to search. A fixed depth or move time is used as is, with a clock a part of the
+/// remaining time plus half the increment is spent. Without any limit the search goes on until
+/// it is stopped.
+pub fn search_limit(parameters: &GoParameters, player: Player) -> SearchLimit {
+    if let Some(depth) = parameters.depth {
+        return SearchLimit::Depth(depth);
+    }
+    if let Some(move_time) = parameters.move_time {
+        return SearchLimit::Time(move_time);
+    }
+    let (remaining, increment) = match player {
+        Player::White => (parameters.white_time, parameters.white_increment),
+        Player::Black => (parameters.black_time, parameters.black_increment),
+    };
+    match remaining {
+        Some(remaining) if !parameters.infinite => {
+            let increment = increment.unwrap_or_default();
+            let time = (remaining / MOVES_TO_GO + increment / 2).min(remaining / 2);
+            SearchLimit::Time(time.max(Duration::from_millis(1)))
+        }
+        _ => SearchLimit::Infinite,
+    }
+}
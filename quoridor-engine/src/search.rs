@@ -0,0 +1,560 @@
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::Boardstate;
+use quoridor_core::gamestate::GameStatus;
+use quoridor_core::locations::Location;
+
+use crate::evaluation::{Evaluator, ShortestPathEvaluator, WIN_SCORE, WIN_THRESHOLD};
+use crate::transposition::{Bound, TableStatistics, TranspositionTable, DEFAULT_TABLE_ENTRIES};
+
+/// The deepest the iterative deepening goes when the search is only limited by time.
+pub const MAX_DEPTH: u8 = 64;
+
+const INFINITY: i32 = WIN_SCORE + 1;
+
+/// The number of nodes searched between checks of the clock.
+const NODES_BETWEEN_TIME_CHECKS: u64 = 256;
+
+/// How often an infinite search that has nothing left to search checks the stop flag.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// When to stop searching. Iterative deepening always finishes the first depth, so even a very
+/// short time limit gives a move.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SearchLimit {
+    Depth(u8),
+    Time(Duration),
+    /// Searches until the stop flag of `Engine::set_stop_flag` is set, the result is held back
+    /// until then even when it is already certain. Without a stop flag this is a search to
+    /// `MAX_DEPTH`.
+    Infinite,
+}
+
+/// The outcome of a search, the score is from the point of view of the player to move and the
+/// principal variation starts with the best action.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    best_action: Action,
+    score: i32,
+    principal_variation: Vec<Action>,
+    depth: u8,
+    nodes: u64,
+}
+
+impl SearchResult {
+    pub fn get_best_action(&self) -> Action {
+        self.best_action
+    }
+
+    pub fn get_score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn get_principal_variation(&self) -> &Vec<Action> {
+        &self.principal_variation
+    }
+
+    /// The depth of the last iteration that was completed.
+    pub fn get_depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// The number of nodes visited over all iterations.
+    pub fn get_nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// The number of plies until the game is won (positive) or lost (negative) when the score is
+    /// a forced result, otherwise None.
+    pub fn get_plies_to_win(&self) -> Option<i32> {
+        if self.score > WIN_THRESHOLD {
+            Some(WIN_SCORE - self.score)
+        } else if self.score < -WIN_THRESHOLD {
+            Some(-(WIN_SCORE + self.score))
+        } else {
+            None
+        }
+    }
+}
+
+/// A negamax alpha-beta search with iterative deepening. Every iteration searches the principal
+/// variation of the previous iteration first, which makes the alpha-beta cut offs a lot more
+/// effective.
+///
+/// The engine is generic over the evaluation of the leaf nodes, `Engine::new` uses the
+/// `ShortestPathEvaluator`.
+///
+/// Searched positions are kept in a transposition table, which is kept between searches so the
+/// next move can reuse the work of the previous one.
+pub struct Engine<E: Evaluator = ShortestPathEvaluator> {
+    evaluator: E,
+    table: TranspositionTable,
+    nodes: u64,
+    deadline: Option<Instant>,
+    stop_flag: Option<Arc<AtomicBool>>,
+    iteration_depth: u8,
+    stopped: bool,
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::with_evaluator(ShortestPathEvaluator::default())
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl<E: Evaluator> Engine<E> {
+    pub fn with_evaluator(evaluator: E) -> Engine<E> {
+        Engine {
+            evaluator,
+            table: TranspositionTable::new(DEFAULT_TABLE_ENTRIES),
+            nodes: 0,
+            deadline: None,
+            stop_flag: None,
+            iteration_depth: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn get_evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    /// Replaces the transposition table by an empty table with room for the number of entries.
+    pub fn set_table_size(&mut self, entries: usize) {
+        self.table = TranspositionTable::new(entries);
+    }
+
+    /// Empties the transposition table, for example when a new game starts.
+    pub fn clear_table(&mut self) {
+        self.table.clear();
+    }
+
+    /// Lets another thread stop the search by setting the flag, the search then returns the result
+    /// of the last finished depth. The flag is never reset by the engine.
+    pub fn set_stop_flag(&mut self, stop_flag: Arc<AtomicBool>) {
+        self.stop_flag = Some(stop_flag);
+    }
+
+    /// The statistics of the transposition table during the last search.
+    pub fn get_table_statistics(&self) -> TableStatistics {
+        self.table.get_statistics()
+    }
+
+    /// Searches the boardstate for the best action for the active player. Returns None when the
+    /// active player has no legal actions.
+    pub fn search(&mut self, boardstate: &Boardstate, limit: SearchLimit) -> Option<SearchResult> {
+        self.search_with_info(boardstate, limit, |_| ())
+    }
+
+    /// The same as `search`, but calls `on_iteration` with the result of every finished depth.
+    pub fn search_with_info(
+        &mut self,
+        boardstate: &Boardstate,
+        limit: SearchLimit,
+        mut on_iteration: impl FnMut(&SearchResult),
+    ) -> Option<SearchResult> {
+        let (max_depth, deadline) = match limit {
+            SearchLimit::Depth(depth) => (depth.clamp(1, MAX_DEPTH), None),
+            SearchLimit::Time(time) => (MAX_DEPTH, Some(Instant::now() + time)),
+            SearchLimit::Infinite => (MAX_DEPTH, None),
+        };
+        self.nodes = 0;
+        self.deadline = deadline;
+        self.stopped = false;
+        self.table.reset_statistics();
+
+        let mut boardstate = boardstate.clone();
+        let mut result: Option<SearchResult> = None;
+        for depth in 1..=max_depth {
+            self.iteration_depth = depth;
+            let previous_variation = result
+                .as_ref()
+                .map(|result| result.principal_variation.clone())
+                .unwrap_or_default();
+            let mut principal_variation = Vec::new();
+            let score = self.negamax(
+                &mut boardstate,
+                depth,
+                0,
+                -INFINITY,
+                INFINITY,
+                &previous_variation,
+                &mut principal_variation,
+            );
+            if self.stopped {
+                break;
+            }
+            let Some(best_action) = principal_variation.first().copied() else {
+                break;
+            };
+            let iteration = SearchResult {
+                best_action,
+                score,
+                principal_variation,
+                depth,
+                nodes: self.nodes,
+            };
+            on_iteration(&iteration);
+            result = Some(iteration);
+            if score.abs() > WIN_THRESHOLD {
+                break;
+            }
+        }
+
+        if limit == SearchLimit::Infinite {
+            self.wait_for_stop();
+        }
+        result
+    }
+
+    /// Holds on to the result of an infinite search that finished early until it is stopped.
+    fn wait_for_stop(&self) {
+        let Some(stop_flag) = &self.stop_flag else {
+            return;
+        };
+        while !stop_flag.load(Ordering::Relaxed) {
+            thread::sleep(STOP_POLL_INTERVAL);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        boardstate: &mut Boardstate,
+        depth: u8,
+        ply: u8,
+        mut alpha: i32,
+        beta: i32,
+        previous_variation: &[Action],
+        principal_variation: &mut Vec<Action>,
+    ) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+        principal_variation.clear();
+        if depth == 0 {
+            return self.evaluate(boardstate);
+        }
+
+        let key = boardstate.get_zobrist_key();
+        let mut table_action = None;
+        if let Some(entry) = self.table.probe(key, ply) {
+            table_action = entry.get_best_action();
+            // The root always searches, it has to come up with a principal variation.
+            if ply > 0 && entry.get_depth() >= depth {
+                let score = entry.get_score();
+                let cut_off = match entry.get_bound() {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+                if cut_off {
+                    principal_variation.extend(table_action);
+                    return score;
+                }
+            }
+        }
+
+        let preferred_action = previous_variation.first().copied().or(table_action);
+        let actions = order_actions(boardstate, preferred_action.as_ref());
+        if actions.is_empty() {
+            return self.evaluate(boardstate);
+        }
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_action = None;
+        let mut child_variation = Vec::new();
+        for action in actions {
+            let Ok((status, undo)) = boardstate.apply_action_reversible(action) else {
+                continue;
+            };
+            let score = match status {
+                GameStatus::Finished { .. } => {
+                    child_variation.clear();
+                    WIN_SCORE - i32::from(ply) - 1
+                }
+                GameStatus::InProgress => {
+                    let child_previous_variation = match previous_variation.split_first() {
+                        Some((first, rest)) if *first == action => rest,
+                        _ => &[],
+                    };
+                    -self.negamax(
+                        boardstate,
+                        depth - 1,
+                        ply + 1,
+                        -beta,
+                        -alpha,
+                        child_previous_variation,
+                        &mut child_variation,
+                    )
+                }
+            };
+            boardstate.unapply_action(undo);
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_action = Some(action);
+                if score > alpha {
+                    alpha = score;
+                    principal_variation.clear();
+                    principal_variation.push(action);
+                    principal_variation.append(&mut child_variation);
+                }
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table
+            .store(key, ply, depth, best_score, bound, best_action);
+
+        best_score
+    }
+
+    fn evaluate(&self, boardstate: &Boardstate) -> i32 {
+        self.evaluator
+            .evaluate(boardstate)
+            .clamp(-WIN_THRESHOLD, WIN_THRESHOLD)
+    }
+
+    /// The clock and stop flag are only checked every so many nodes, and never during the first
+    /// iteration.
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped
+            && self.iteration_depth > 1
+            && self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS)
+        {
+            let time_is_up = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+            let stop_requested = self
+                .stop_flag
+                .as_ref()
+                .is_some_and(|stop_flag| stop_flag.load(Ordering::Relaxed));
+            self.stopped = time_is_up || stop_requested;
+        }
+        self.stopped
+    }
+}
+
+/// Orders the legal actions so the most promising ones are searched first: the action from the
+/// previous principal variation or the transposition table, then the pawn moves that get closest
+/// to the goal, then the walls that touch the shortest path of the opponent and finally all the
+/// other walls.
+fn order_actions(boardstate: &Boardstate, preferred_action: Option<&Action>) -> Vec<Action> {
+    let player = boardstate.get_active_player();
+    let distances = boardstate.get_distance_to_goal_map(player);
+    let mut opponent_path = [false; 81];
+    if let Some(shortest_path) = boardstate.get_shortest_path(player.get_opponent()) {
+        for location in shortest_path.get_path() {
+            opponent_path[usize::from(location.get_square())] = true;
+        }
+    }
+    opponent_path[usize::from(
+        boardstate
+            .get_position_pawn(player.get_opponent())
+            .get_square(),
+    )] = true;
+
+    let mut actions: Vec<(i32, Action)> = boardstate
+        .get_legal_actions()
+        .get_actions()
+        .iter()
+        .map(|action| {
+            let order = if Some(action) == preferred_action {
+                i32::MAX
+            } else {
+                match action {
+                    Action::Pawn(location) => {
+                        1_000
+                            - i32::from(
+                                distances[usize::from(location.get_square())].unwrap_or(u8::MAX),
+                            )
+                    }
+                    Action::Wall(location) => {
+                        let square = location.get_square();
+                        let touches_path = [square, square + 1, square + 9, square + 10]
+                            .iter()
+                            .any(|square| opponent_path[usize::from(*square)]);
+                        if touches_path {
+                            1
+                        } else {
+                            0
+                        }
+                    }
+                    Action::Resigned(_) => i32::MIN,
+                }
+            };
+            (order, *action)
+        })
+        .collect();
+    actions.sort_by_key(|(order, _)| Reverse(*order));
+
+    actions.into_iter().map(|(_, action)| action).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quoridor_core::boardstate::Player;
+    use quoridor_core::locations::{PawnLocation, WallLocation, WallOrientation};
+
+    use super::*;
+
+    #[test]
+    fn takes_the_winning_move() {
+        let boardstate = Boardstate::from_position_notation("E8 A5 - 10 10 w").unwrap();
+        let result = Engine::new()
+            .search(&boardstate, SearchLimit::Depth(3))
+            .unwrap();
+        assert_eq!(
+            result.get_best_action(),
+            Action::Pawn(PawnLocation::build(76).unwrap())
+        );
+        assert_eq!(result.get_score(), WIN_SCORE - 1);
+        assert_eq!(result.get_plies_to_win(), Some(1));
+        assert_eq!(result.get_principal_variation().len(), 1);
+    }
+
+    #[test]
+    fn blocks_the_opponent_from_winning() {
+        let boardstate = Boardstate::from_position_notation("A1 E2 - 10 10 w").unwrap();
+        let result = Engine::new()
+            .search(&boardstate, SearchLimit::Depth(2))
+            .unwrap();
+        assert!(matches!(result.get_best_action(), Action::Wall(_)));
+        assert!(result.get_plies_to_win().is_none());
+    }
+
+    #[test]
+    fn principal_variation_is_legal() {
+        let boardstate = Boardstate::from_position_notation("E3 E7 - 10 10 w").unwrap();
+        let result = Engine::new()
+            .search(&boardstate, SearchLimit::Depth(2))
+            .unwrap();
+        assert_eq!(result.get_depth(), 2);
+        assert_eq!(
+            result.get_principal_variation().first(),
+            Some(&result.get_best_action())
+        );
+
+        let mut boardstate = boardstate;
+        for action in result.get_principal_variation() {
+            boardstate.apply_action(*action).unwrap();
+        }
+    }
+
+    #[test]
+    fn search_uses_the_evaluator() {
+        // An evaluator that only likes walls in hand never places one.
+        let walls_in_hand = |boardstate: &Boardstate| {
+            let white = i32::from(boardstate.get_available_walls_white_player());
+            let black = i32::from(boardstate.get_available_walls_black_player());
+            match boardstate.get_active_player() {
+                Player::White => white - black,
+                Player::Black => black - white,
+            }
+        };
+        let result = Engine::with_evaluator(walls_in_hand)
+            .search(&Boardstate::new(), SearchLimit::Depth(1))
+            .unwrap();
+        assert!(matches!(result.get_best_action(), Action::Pawn(_)));
+
+        let result = Engine::with_evaluator(|_: &Boardstate| WIN_SCORE)
+            .search(&Boardstate::new(), SearchLimit::Depth(1))
+            .unwrap();
+        assert_eq!(result.get_score(), -WIN_THRESHOLD);
+    }
+
+    #[test]
+    fn transposition_table_is_reused_between_searches() {
+        let boardstate = Boardstate::from_position_notation("E3 E7 - 10 10 w").unwrap();
+        let mut engine = Engine::new();
+        let first = engine.search(&boardstate, SearchLimit::Depth(2)).unwrap();
+        let first_statistics = engine.get_table_statistics();
+        assert!(first_statistics.get_stores() > 0);
+
+        let second = engine.search(&boardstate, SearchLimit::Depth(2)).unwrap();
+        assert_eq!(second.get_score(), first.get_score());
+        assert!(second.get_nodes() < first.get_nodes());
+        assert!(engine.get_table_statistics().get_hit_rate() > first_statistics.get_hit_rate());
+    }
+
+    #[test]
+    fn search_reports_every_depth() {
+        let mut depths = Vec::new();
+        Engine::new().search_with_info(&Boardstate::new(), SearchLimit::Depth(2), |result| {
+            depths.push(result.get_depth())
+        });
+        assert_eq!(depths, vec![1, 2]);
+    }
+
+    #[test]
+    fn stop_flag_stops_the_search() {
+        let stop_flag = Arc::new(AtomicBool::new(true));
+        let mut engine = Engine::new();
+        engine.set_stop_flag(stop_flag);
+        let result = engine
+            .search(&Boardstate::new(), SearchLimit::Depth(MAX_DEPTH))
+            .unwrap();
+        assert_eq!(result.get_depth(), 1);
+    }
+
+    #[test]
+    fn search_stops_at_the_time_limit() {
+        let start = Instant::now();
+        let result = Engine::new()
+            .search(
+                &Boardstate::new(),
+                SearchLimit::Time(Duration::from_millis(50)),
+            )
+            .unwrap();
+        assert!(result.get_depth() >= 1);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn walls_on_the_opponent_path_are_ordered_first() {
+        let boardstate = Boardstate::new();
+        let actions = order_actions(&boardstate, None);
+        assert!(matches!(actions[0], Action::Pawn(_)));
+        let first_wall = actions
+            .iter()
+            .find(|action| matches!(action, Action::Wall(_)))
+            .unwrap();
+        let Action::Wall(location) = first_wall else {
+            unreachable!()
+        };
+        assert_eq!(location.get_square() % 9, 3);
+
+        let principal_action =
+            Action::Wall(WallLocation::build(0, WallOrientation::Vertical).unwrap());
+        assert_eq!(
+            order_actions(&boardstate, Some(&principal_action))[0],
+            principal_action
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use quoridor_core::actions::Action;
+
+use crate::evaluation::WIN_THRESHOLD;
+
+/// The number of entries of the table of a new engine, a bit over 8 MB.
+pub const DEFAULT_TABLE_ENTRIES: usize = 1 << 18;
+
+/// What the stored score says about the real score of the position. When the search is cut off
+/// by alpha-beta only a bound of the score is known.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Bound {
+    /// The score is the real score.
+    Exact,
+    /// The real score is at least the score, the search failed high.
+    Lower,
+    /// The real score is at most the score, the search failed low.
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TableEntry {
+    key: u64,
+    depth: u8,
+    score: i32,
+    bound: Bound,
+    best_action: Option<Action>,
+}
+
+impl TableEntry {
+    /// The depth of the search below the position that gave the score.
+    pub fn get_depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// The score of the position, forced wins count the plies from the position itself.
+    pub fn get_score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn get_bound(&self) -> Bound {
+        self.bound
+    }
+
+    pub fn get_best_action(&self) -> Option<Action> {
+        self.best_action
+    }
+}
+
+/// How well the table is doing since the statistics were last reset.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TableStatistics {
+    probes: u64,
+    hits: u64,
+    stores: u64,
+    replacements: u64,
+}
+
+impl TableStatistics {
+    pub fn get_probes(&self) -> u64 {
+        self.probes
+    }
+
+    /// The number of probes that found an entry for the position.
+    pub fn get_hits(&self) -> u64 {
+        self.hits
+    }
+
+    pub fn get_stores(&self) -> u64 {
+        self.stores
+    }
+
+    /// The number of stores that overwrote an entry of another position.
+    pub fn get_replacements(&self) -> u64 {
+        self.replacements
+    }
+
+    /// The fraction of the probes that found an entry, 0 when nothing was probed yet.
+    pub fn get_hit_rate(&self) -> f64 {
+        if self.probes == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.probes as f64
+    }
+}
+
+/// A fixed size hash table of searched positions, indexed by the Zobrist key of the boardstate.
+/// The same position is reached by placing the same walls in a different order, the table lets
+/// the search reuse the result instead of searching the position again.
+///
+/// Every position maps to a single slot. When two positions want the same slot the one searched
+/// to the larger depth is kept, since it saved the most work.
+pub struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+    statistics: TableStatistics,
+}
+
+impl TranspositionTable {
+    /// Creates a table with room for the number of entries, at least one.
+    pub fn new(entries: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: vec![None; entries.max(1)],
+            statistics: TableStatistics::default(),
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get_statistics(&self) -> TableStatistics {
+        self.statistics
+    }
+
+    pub fn reset_statistics(&mut self) {
+        self.statistics = TableStatistics::default();
+    }
+
+    /// Removes all the entries, the statistics are kept.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    /// Looks up the position with the key. The score of the entry is already corrected for the
+    /// ply the position is found at.
+    pub fn probe(&mut self, key: u64, ply: u8) -> Option<TableEntry> {
+        self.statistics.probes += 1;
+        let entry = self.entries[self.index(key)].filter(|entry| entry.key == key)?;
+        self.statistics.hits += 1;
+
+        Some(TableEntry {
+            score: score_from_table(entry.score, ply),
+            ..entry
+        })
+    }
+
+    /// Stores the result of searching the position with the key, unless the slot holds another
+    /// position that was searched deeper.
+    pub fn store(
+        &mut self,
+        key: u64,
+        ply: u8,
+        depth: u8,
+        score: i32,
+        bound: Bound,
+        best_action: Option<Action>,
+    ) {
+        let index = self.index(key);
+        if let Some(existing) = self.entries[index] {
+            if existing.key != key {
+                if existing.depth > depth {
+                    return;
+                }
+                self.statistics.replacements += 1;
+            }
+        }
+
+        self.statistics.stores += 1;
+        self.entries[index] = Some(TableEntry {
+            key,
+            depth,
+            score: score_to_table(score, ply),
+            bound,
+            best_action,
+        });
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+/// Scores of forced wins count the plies from the root of the search. In the table they are
+/// stored counting from the position itself, since the position can be reached at another ply.
+fn score_to_table(score: i32, ply: u8) -> i32 {
+    if score > WIN_THRESHOLD {
+        score + i32::from(ply)
+    } else if score < -WIN_THRESHOLD {
+        score - i32::from(ply)
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: u8) -> i32 {
+    if score > WIN_THRESHOLD {
+        score - i32::from(ply)
+    } else if score < -WIN_THRESHOLD {
+        score + i32::from(ply)
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quoridor_core::locations::PawnLocation;
+
+    use super::*;
+    use crate::evaluation::WIN_SCORE;
+
+    #[test]
+    fn stored_entries_are_found() {
+        let mut table = TranspositionTable::new(16);
+        let action = Action::Pawn(PawnLocation::build(13).unwrap());
+        table.store(35, 0, 3, 120, Bound::Exact, Some(action));
+
+        let entry = table.probe(35, 0).unwrap();
+        assert_eq!(entry.get_depth(), 3);
+        assert_eq!(entry.get_score(), 120);
+        assert_eq!(entry.get_bound(), Bound::Exact);
+        assert_eq!(entry.get_best_action(), Some(action));
+
+        // Same slot, different position.
+        assert_eq!(table.probe(51, 0), None);
+        assert_eq!(table.get_statistics().get_probes(), 2);
+        assert_eq!(table.get_statistics().get_hits(), 1);
+        assert_eq!(table.get_statistics().get_hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn deeper_entries_are_kept() {
+        let mut table = TranspositionTable::new(16);
+        table.store(35, 0, 4, 10, Bound::Exact, None);
+        table.store(51, 0, 2, 20, Bound::Exact, None);
+        assert_eq!(table.probe(35, 0).unwrap().get_score(), 10);
+
+        table.store(51, 0, 5, 20, Bound::Lower, None);
+        assert_eq!(table.probe(35, 0), None);
+        assert_eq!(table.probe(51, 0).unwrap().get_bound(), Bound::Lower);
+        assert_eq!(table.get_statistics().get_replacements(), 1);
+
+        // The same position is always overwritten, the newer search is more accurate.
+        table.store(51, 0, 1, 30, Bound::Upper, None);
+        assert_eq!(table.probe(51, 0).unwrap().get_score(), 30);
+    }
+
+    #[test]
+    fn win_scores_are_relative_to_the_position() {
+        let mut table = TranspositionTable::new(16);
+        table.store(7, 3, 2, WIN_SCORE - 5, Bound::Exact, None);
+        assert_eq!(table.probe(7, 1).unwrap().get_score(), WIN_SCORE - 3);
+        table.store(7, 3, 2, -WIN_SCORE + 5, Bound::Exact, None);
+        assert_eq!(table.probe(7, 1).unwrap().get_score(), -WIN_SCORE + 3);
+    }
+
+    #[test]
+    fn clear_removes_the_entries() {
+        let mut table = TranspositionTable::new(16);
+        table.store(7, 0, 2, 5, Bound::Exact, None);
+        table.clear();
+        assert_eq!(table.probe(7, 0), None);
+    }
+}
@@ -1,27 +1,571 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tokio::sync::{broadcast, Notify};
 use uuid::Uuid;
 
-use quoridor_core::gamestate::{Gamestate, TimeControl};
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::Player;
+use quoridor_core::error::QuoridorError;
+use quoridor_core::gamestate::{GameStatus, Gamestate, TimeControl};
+
+use crate::player::{AnonUser, PlayerInfo};
+use crate::protocol::{Colour, PerPlayer, ServerMessage, StateUpdate, StatusUpdate};
+
+/// The number of updates a slow connection can fall behind before it misses updates.
+const UPDATE_CAPACITY: usize = 32;
+
+/// How often the clocks of a running game are sent to the clients.
+pub const CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The colour the creator of a game wants to play with.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColourPreference {
+    White,
+    Black,
+    #[default]
+    Random,
+}
+
+impl ColourPreference {
+    pub fn to_player(self) -> Player {
+        match self {
+            ColourPreference::White => Player::White,
+            ColourPreference::Black => Player::Black,
+            ColourPreference::Random if rand::random() => Player::White,
+            ColourPreference::Random => Player::Black,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum GameError {
+    /// Both seats of the game are already taken.
+    GameFull,
+    /// The game waits for a second player, no actions can be played yet.
+    NotStarted,
+    /// Only the players seated in the game can play actions.
+    NotSeated,
+    /// The action was refused by the gamestate.
+    Quoridor(QuoridorError),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::GameFull => write!(f, "Both seats of the game are taken"),
+            GameError::NotStarted => write!(f, "The game waits for an opponent"),
+            GameError::NotSeated => write!(f, "You are not playing in this game"),
+            GameError::Quoridor(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl From<QuoridorError> for GameError {
+    fn from(error: QuoridorError) -> Self {
+        GameError::Quoridor(error)
+    }
+}
 
-use crate::player::AnonUser;
+/// The sockets a seated player has open to the game, a player can have the game open in more than
+/// one tab.
+#[derive(Copy, Clone, Debug, Default)]
+struct SeatConnection {
+    sockets: usize,
+    disconnected_since: Option<Instant>,
+}
 
+/// A game between two players on the platform. The game is created with only the creator
+/// seated, and starts when a second player takes the open seat.
 pub struct Game {
     id: Uuid,
-    white_player: AnonUser,
-    black_player: AnonUser,
+    white_player: Option<AnonUser>,
+    black_player: Option<AnonUser>,
+    white_connection: SeatConnection,
+    black_connection: SeatConnection,
+    time_control: TimeControl,
     gamestate: Gamestate,
+    updates: broadcast::Sender<ServerMessage>,
+    clock_changed: Arc<Notify>,
 }
 
 impl Game {
-    pub fn new(player_1: AnonUser, player_2: AnonUser, time_control: TimeControl) -> Self {
-        Game {
+    pub fn new(creator: AnonUser, colour: Player, time_control: TimeControl) -> Self {
+        let (updates, _) = broadcast::channel(UPDATE_CAPACITY);
+        let mut game = Game {
             id: Uuid::new_v4(),
-            white_player: player_1,
-            black_player: player_2,
+            white_player: None,
+            black_player: None,
+            white_connection: SeatConnection::default(),
+            black_connection: SeatConnection::default(),
+            time_control,
             gamestate: Gamestate::new(time_control),
-        }
+            updates,
+            clock_changed: Arc::new(Notify::new()),
+        };
+        *game.get_seat_mut(colour) = Some(creator);
+        game
     }
 
     pub fn get_id(&self) -> Uuid {
         self.id
     }
+
+    pub fn get_time_control(&self) -> TimeControl {
+        self.time_control
+    }
+
+    /// The player seated with the colour, None while the seat is open.
+    pub fn get_player(&self, colour: Player) -> Option<&AnonUser> {
+        match colour {
+            Player::White => self.white_player.as_ref(),
+            Player::Black => self.black_player.as_ref(),
+        }
+    }
+
+    fn get_seat_mut(&mut self, colour: Player) -> &mut Option<AnonUser> {
+        match colour {
+            Player::White => &mut self.white_player,
+            Player::Black => &mut self.black_player,
+        }
+    }
+
+    fn get_connection_mut(&mut self, colour: Player) -> &mut SeatConnection {
+        match colour {
+            Player::White => &mut self.white_connection,
+            Player::Black => &mut self.black_connection,
+        }
+    }
+
+    /// Whether the player in the seat has at least one socket connected to the game.
+    pub fn is_connected(&self, colour: Player) -> bool {
+        let connection = match colour {
+            Player::White => &self.white_connection,
+            Player::Black => &self.black_connection,
+        };
+        connection.sockets > 0
+    }
+
+    /// The colour of the seat the user holds, None for users that don't play in the game.
+    pub fn get_seat(&self, user_id: &Uuid) -> Option<Player> {
+        [Player::White, Player::Black].into_iter().find(|&colour| {
+            self.get_player(colour)
+                .is_some_and(|user| user.get_id() == user_id)
+        })
+    }
+
+    /// The colour of the open seat, None once both players are seated.
+    pub fn get_open_seat(&self) -> Option<Player> {
+        [Player::White, Player::Black]
+            .into_iter()
+            .find(|&colour| self.get_player(colour).is_none())
+    }
+
+    /// A game has started once both players are seated.
+    pub fn is_started(&self) -> bool {
+        self.get_open_seat().is_none()
+    }
+
+    pub fn get_gamestate(&self) -> &Gamestate {
+        &self.gamestate
+    }
+
+    /// The full state of the game, which is also what a reconnecting client needs to catch up.
+    pub fn get_state_update(&self) -> ServerMessage {
+        let mut update = if self.is_started() {
+            StateUpdate::from_gamestate(self.id, &self.gamestate)
+        } else {
+            // The clocks only start when the game starts, so a waiting game shows the full time.
+            let mut update =
+                StateUpdate::from_gamestate(self.id, &Gamestate::new(self.time_control));
+            update.status = StatusUpdate::WaitingForOpponent;
+            update
+        };
+        update.connected = PerPlayer {
+            white: self.is_connected(Player::White),
+            black: self.is_connected(Player::Black),
+        };
+        ServerMessage::State(update)
+    }
+
+    /// Notified when an action switched the running clock, so the clock task of the game can
+    /// reschedule the moment the active player runs out of time.
+    pub fn get_clock_changed(&self) -> Arc<Notify> {
+        self.clock_changed.clone()
+    }
+
+    /// Receives every update of the game from now on, the current state is not included.
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerMessage> {
+        self.updates.subscribe()
+    }
+
+    /// Seats the player in the open seat and starts the game, the clocks start running from this
+    /// moment. Returns the colour the player plays with, a player that is already seated keeps
+    /// its seat. A player without a socket to the game counts as disconnected from the start, so
+    /// a player that never opens the game abandons it after the grace period.
+    pub fn join(&mut self, player: AnonUser) -> Result<Player, GameError> {
+        if let Some(colour) = self.get_seat(player.get_id()) {
+            return Ok(colour);
+        }
+        let colour = self.get_open_seat().ok_or(GameError::GameFull)?;
+        *self.get_seat_mut(colour) = Some(player);
+        self.gamestate = Gamestate::new(self.time_control);
+        let now = Instant::now();
+        for connection in [&mut self.white_connection, &mut self.black_connection] {
+            if connection.sockets == 0 {
+                connection.disconnected_since = Some(now);
+            }
+        }
+        self.broadcast(self.get_state_update());
+        Ok(colour)
+    }
+
+    /// Counts a socket of the user as connected to the game. The first socket of a seated player
+    /// tells the subscribers the player is connected. Returns the seat of the user, None for
+    /// spectators which are not counted.
+    pub fn connect(&mut self, user_id: &Uuid) -> Option<Player> {
+        let colour = self.get_seat(user_id)?;
+        let connection = self.get_connection_mut(colour);
+        connection.sockets += 1;
+        connection.disconnected_since = None;
+        if connection.sockets == 1 {
+            self.broadcast_connection(colour);
+        }
+        Some(colour)
+    }
+
+    /// Counts a socket of the seated player as gone. When it was the last socket of the player
+    /// the subscribers are told the player disconnected, and true is returned so the caller can
+    /// start the grace period of `abandon_if_disconnected`.
+    pub fn disconnect(&mut self, colour: Player) -> bool {
+        let connection = self.get_connection_mut(colour);
+        connection.sockets = connection.sockets.saturating_sub(1);
+        if connection.sockets > 0 {
+            return false;
+        }
+        connection.disconnected_since = Some(Instant::now());
+        self.broadcast_connection(colour);
+        true
+    }
+
+    /// Ends the game as abandoned by the player when the player has been disconnected for at
+    /// least the grace period. Returns whether the game was ended.
+    pub fn abandon_if_disconnected(&mut self, colour: Player, grace_period: Duration) -> bool {
+        let connection = self.get_connection_mut(colour);
+        let abandoned = connection
+            .disconnected_since
+            .is_some_and(|since| since.elapsed() >= grace_period);
+        if !abandoned || !self.is_started() || self.gamestate.abandon(colour).is_err() {
+            return false;
+        }
+        self.broadcast(self.get_state_update());
+        true
+    }
+
+    /// Executes the action in notation for the seat of the user and sends the new state to every
+    /// subscriber. Nothing is sent when the action is refused. The clock of the player runs until
+    /// the moment the server received the action, so the player doesn't pay for the time the
+    /// server needed to get to it.
+    pub fn execute_action(
+        &mut self,
+        user_id: &Uuid,
+        notation: &str,
+        received_at: Instant,
+    ) -> Result<GameStatus, GameError> {
+        if !self.is_started() {
+            return Err(GameError::NotStarted);
+        }
+        let player = self.get_seat(user_id).ok_or(GameError::NotSeated)?;
+        let action = Action::from_notation(notation)?;
+        let status = self
+            .gamestate
+            .execute_received_action(player, action, received_at)?;
+        self.broadcast(self.get_state_update());
+        self.clock_changed.notify_one();
+        Ok(status)
+    }
+
+    /// Ends the game when the active player ran out of time, otherwise sends the clocks to the
+    /// subscribers. Returns how long to wait before updating the clocks again, None when the
+    /// clocks don't need updates because the game has no clock or is finished.
+    pub fn update_clocks(&mut self) -> Option<Duration> {
+        if !self.is_started() || self.gamestate.get_status() != GameStatus::InProgress {
+            return None;
+        }
+        if self.gamestate.check_flag() != GameStatus::InProgress {
+            self.broadcast(self.get_state_update());
+            return None;
+        }
+
+        let active_player = self.gamestate.get_boardstate().get_active_player();
+        let white = self.gamestate.get_remaining_time(Player::White)?;
+        let black = self.gamestate.get_remaining_time(Player::Black)?;
+        self.broadcast(ServerMessage::Clock {
+            clocks: PerPlayer {
+                white: white.as_millis() as u64,
+                black: black.as_millis() as u64,
+            },
+            active_player: Colour::from(active_player),
+        });
+        let remaining = match active_player {
+            Player::White => white,
+            Player::Black => black,
+        };
+        Some(remaining.min(CLOCK_SYNC_INTERVAL))
+    }
+
+    fn broadcast_connection(&self, colour: Player) {
+        self.broadcast(ServerMessage::Connection {
+            colour: Colour::from(colour),
+            connected: self.is_connected(colour),
+        });
+    }
+
+    fn broadcast(&self, message: ServerMessage) {
+        // Sending only fails when nobody is subscribed, then there is nobody to tell.
+        let _ = self.updates.send(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quoridor_core::gamestate::VictoryReason;
+
+    use super::*;
+
+    /// A started game with the two players, white first.
+    fn game() -> (Game, AnonUser, AnonUser) {
+        let white = AnonUser::new();
+        let black = AnonUser::new();
+        let mut game = Game::new(white.clone(), Player::White, TimeControl::Unlimited);
+        game.join(black.clone()).unwrap();
+        (game, white, black)
+    }
+
+    #[test]
+    fn second_player_takes_the_open_seat() {
+        let creator = AnonUser::new();
+        let mut game = Game::new(creator.clone(), Player::Black, TimeControl::Unlimited);
+        let mut receiver = game.subscribe();
+        assert_eq!(game.get_open_seat(), Some(Player::White));
+        assert!(!game.is_started());
+        assert_eq!(
+            game.execute_action(creator.get_id(), "E2", Instant::now()),
+            Err(GameError::NotStarted)
+        );
+        let ServerMessage::State(update) = game.get_state_update() else {
+            panic!("Expected a state update");
+        };
+        assert_eq!(update.status, StatusUpdate::WaitingForOpponent);
+
+        // The creator opening its own challenge link doesn't take the other seat.
+        assert_eq!(game.join(creator.clone()), Ok(Player::Black));
+        assert!(!game.is_started());
+
+        let opponent = AnonUser::new();
+        assert_eq!(game.join(opponent.clone()), Ok(Player::White));
+        assert!(game.is_started());
+        assert_eq!(game.get_seat(opponent.get_id()), Some(Player::White));
+        let Ok(ServerMessage::State(update)) = receiver.try_recv() else {
+            panic!("Expected a state update when the game starts");
+        };
+        assert_eq!(update.status, StatusUpdate::InProgress);
+
+        assert_eq!(game.join(AnonUser::new()), Err(GameError::GameFull));
+        assert_eq!(game.join(opponent), Ok(Player::White));
+    }
+
+    #[test]
+    fn actions_are_sent_to_subscribers() {
+        let (mut game, white, _) = game();
+        let mut first = game.subscribe();
+        let mut second = game.subscribe();
+
+        assert_eq!(
+            game.execute_action(white.get_id(), "E2", Instant::now()),
+            Ok(GameStatus::InProgress)
+        );
+        for receiver in [&mut first, &mut second] {
+            let Ok(ServerMessage::State(update)) = receiver.try_recv() else {
+                panic!("Expected a state update");
+            };
+            assert_eq!(update.game_id, game.get_id());
+            assert_eq!(update.last_move, Some(String::from("E2")));
+            assert_eq!(update.status, StatusUpdate::InProgress);
+        }
+    }
+
+    #[test]
+    fn actions_are_authorised_by_seat() {
+        let (mut game, white, black) = game();
+        let mut receiver = game.subscribe();
+
+        assert!(matches!(
+            game.execute_action(black.get_id(), "E8", Instant::now()),
+            Err(GameError::Quoridor(QuoridorError::NotYourTurn { .. }))
+        ));
+        assert_eq!(
+            game.execute_action(AnonUser::new().get_id(), "E2", Instant::now()),
+            Err(GameError::NotSeated)
+        );
+        assert!(game
+            .execute_action(white.get_id(), "E5", Instant::now())
+            .is_err());
+        assert!(game
+            .execute_action(white.get_id(), "Z9", Instant::now())
+            .is_err());
+        assert!(game.get_gamestate().get_moves().is_empty());
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn non_ascii_notation_is_refused() {
+        let (mut game, white, _) = game();
+        for notation in ["é", "Eé", "é2", "E2é", "€"] {
+            assert!(
+                matches!(
+                    game.execute_action(white.get_id(), notation, Instant::now()),
+                    Err(GameError::Quoridor(QuoridorError::InvalidNotation { .. }))
+                ),
+                "{notation}"
+            );
+        }
+        assert!(game.get_gamestate().get_moves().is_empty());
+    }
+
+    #[test]
+    fn connections_are_counted_per_seat() {
+        let (mut game, white, _) = game();
+        let mut receiver = game.subscribe();
+        let connection =
+            |receiver: &mut broadcast::Receiver<ServerMessage>| match receiver.try_recv() {
+                Ok(ServerMessage::Connection { colour, connected }) => Some((colour, connected)),
+                _ => None,
+            };
+
+        assert_eq!(game.connect(AnonUser::new().get_id()), None);
+        assert!(receiver.try_recv().is_err());
+
+        // Only the first socket and the last socket of the player are announced.
+        assert_eq!(game.connect(white.get_id()), Some(Player::White));
+        assert_eq!(game.connect(white.get_id()), Some(Player::White));
+        assert_eq!(connection(&mut receiver), Some((Colour::White, true)));
+        assert!(receiver.try_recv().is_err());
+        assert!(!game.disconnect(Player::White));
+        assert!(game.is_connected(Player::White));
+        assert!(game.disconnect(Player::White));
+        assert!(!game.is_connected(Player::White));
+        assert_eq!(connection(&mut receiver), Some((Colour::White, false)));
+
+        let ServerMessage::State(update) = game.get_state_update() else {
+            panic!("Expected a state update");
+        };
+        assert_eq!(
+            update.connected,
+            PerPlayer {
+                white: false,
+                black: false
+            }
+        );
+    }
+
+    #[test]
+    fn disconnected_players_abandon_after_the_grace_period() {
+        let (mut game, white, black) = game();
+        game.connect(white.get_id());
+        game.connect(black.get_id());
+        let grace_period = Duration::from_millis(20);
+
+        assert!(!game.abandon_if_disconnected(Player::White, Duration::ZERO));
+        game.disconnect(Player::White);
+        assert!(!game.abandon_if_disconnected(Player::White, grace_period));
+
+        // Coming back in time cancels the abandonment.
+        game.connect(white.get_id());
+        std::thread::sleep(grace_period);
+        assert!(!game.abandon_if_disconnected(Player::White, grace_period));
+
+        game.disconnect(Player::Black);
+        std::thread::sleep(grace_period);
+        assert!(game.abandon_if_disconnected(Player::Black, grace_period));
+        assert_eq!(
+            game.get_gamestate().get_status(),
+            GameStatus::Finished {
+                won_by: Player::White,
+                reason: VictoryReason::Abandoned
+            }
+        );
+        assert!(!game.abandon_if_disconnected(Player::Black, grace_period));
+    }
+
+    #[test]
+    fn players_that_never_connect_abandon_after_the_grace_period() {
+        let white = AnonUser::new();
+        let mut game = Game::new(white.clone(), Player::White, TimeControl::Unlimited);
+        game.connect(white.get_id());
+        game.join(AnonUser::new()).unwrap();
+
+        assert!(!game.abandon_if_disconnected(Player::White, Duration::ZERO));
+        assert!(game.abandon_if_disconnected(Player::Black, Duration::ZERO));
+        assert_eq!(
+            game.get_gamestate().get_status(),
+            GameStatus::Finished {
+                won_by: Player::White,
+                reason: VictoryReason::Abandoned
+            }
+        );
+    }
+
+    #[test]
+    fn clocks_are_synced_until_the_flag() {
+        let (mut game, _, _) = game();
+        assert_eq!(game.update_clocks(), None);
+
+        let mut game = Game::new(
+            AnonUser::new(),
+            Player::White,
+            TimeControl::Timed {
+                seconds: 60,
+                increment: 0,
+            },
+        );
+        assert_eq!(game.update_clocks(), None);
+        game.join(AnonUser::new()).unwrap();
+        let mut receiver = game.subscribe();
+        assert!(game.update_clocks().unwrap() <= CLOCK_SYNC_INTERVAL);
+        let Ok(ServerMessage::Clock {
+            clocks,
+            active_player,
+        }) = receiver.try_recv()
+        else {
+            panic!("Expected a clock sync");
+        };
+        assert_eq!(active_player, Colour::White);
+        assert_eq!(clocks.black, 60_000);
+
+        let mut game = Game::new(
+            AnonUser::new(),
+            Player::White,
+            TimeControl::Timed {
+                seconds: 0,
+                increment: 0,
+            },
+        );
+        game.join(AnonUser::new()).unwrap();
+        let mut receiver = game.subscribe();
+        assert_eq!(game.update_clocks(), None);
+        let Ok(ServerMessage::State(update)) = receiver.try_recv() else {
+            panic!("Expected a state update");
+        };
+        assert_eq!(
+            update.status,
+            StatusUpdate::Finished {
+                winner: Colour::Black,
+                reason: String::from("out_of_time")
+            }
+        );
+    }
 }
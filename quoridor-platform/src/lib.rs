@@ -1,2 +1,4 @@
 pub mod game;
 pub mod player;
+pub mod protocol;
+pub mod session;
@@ -1,80 +1,483 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    env,
+    process::ExitCode,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
 };
 
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        State, WebSocketUpgrade,
+        Path, State, WebSocketUpgrade,
     },
-    http::HeaderMap,
-    response::IntoResponse,
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
     routing::{get, post, Router},
+    Json,
 };
 use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tower_http::services::ServeFile;
+use tower_sessions::Session;
 use uuid::Uuid;
 
+use quoridor_core::boardstate::Player;
+use quoridor_core::gamestate::TimeControl;
 use quoridor_platform::{
-    game::Game,
-    player::{AnonUser, PlayerType},
+    game::{ColourPreference, Game},
+    player::{AnonUser, PlayerInfo},
+    protocol::{ClientMessage, Colour, ServerMessage},
+    session::{ensure_anon_user, get_anon_user, session_layer},
 };
 
+const USAGE: &str = "Usage: quoridor-platform [--grace-period SECONDS]
+
+Serves the platform on 127.0.0.1:3000. A player that stays disconnected from a running game for
+longer than the grace period, 60 seconds by default, loses the game by abandoning it.";
+
 struct AppState {
     games: Mutex<HashMap<Uuid, Game>>,
+    /// How long a player can be disconnected from a running game before abandoning it.
+    grace_period: Duration,
+}
+
+impl AppState {
+    /// Locks the games. A handler that panicked while holding the lock only leaves its own game
+    /// in doubt, so the lock is recovered instead of failing every game on the server.
+    fn get_games(&self) -> MutexGuard<'_, HashMap<Uuid, Game>> {
+        self.games.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
+    let grace_period = match parse_arguments(env::args().skip(1).collect()) {
+        Ok(grace_period) => grace_period,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
     let state = Arc::new(AppState {
         games: Mutex::new(HashMap::new()),
+        grace_period,
     });
 
-    let app = Router::new()
-        .route_service("/", ServeFile::new("assets/index.html"))
-        .route("/api/v1/new_game", post(new_game))
-        .route("/ws", get(websocket_start))
-        .with_state(state);
-
     let listener = tokio::net::TcpListener::bind("127.0.0.1:3000")
         .await
         .unwrap();
     println!("listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app.into_make_service())
+    axum::serve(listener, app(state).into_make_service())
         .await
         .unwrap();
+    ExitCode::SUCCESS
+}
+
+/// The routes of the platform. Every request gets an anonymous identity before it is handled.
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route_service("/", ServeFile::new("assets/index.html"))
+        .route_service("/games/:game_id", ServeFile::new("assets/index.html"))
+        .route("/api/v1/games", post(create_game))
+        .route("/api/v1/games/:game_id/join", post(join_game))
+        .route("/ws", get(websocket_start))
+        .with_state(state)
+        .layer(middleware::from_fn(ensure_anon_user))
+        .layer(session_layer())
+}
+
+/// Reads the grace period from the arguments.
+fn parse_arguments(arguments: Vec<String>) -> Result<Duration, String> {
+    let mut grace_period = Duration::from_secs(60);
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "--grace-period" => {
+                let value = arguments
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {argument}"))?;
+                let seconds = value
+                    .parse()
+                    .map_err(|_| format!("Invalid number {value}"))?;
+                grace_period = Duration::from_secs(seconds);
+            }
+            _ => return Err(format!("Unknown argument {argument}")),
+        }
+    }
+    Ok(grace_period)
+}
+
+/// The body of a request for a new game, the time control is in the notation of
+/// `TimeControl::from_notation`, like `300+5`.
+#[derive(Deserialize)]
+struct NewGameRequest {
+    time_control: String,
+    #[serde(default)]
+    colour: ColourPreference,
 }
 
-async fn new_game(State(state): State<Arc<AppState>>, headers: HeaderMap) -> impl IntoResponse {
-    // Need a way to start off the game with only 1 player known, also it is possible that players
-    // are not users but maybe just anonymous
-    let player_1 = AnonUser::new());
-    state
-        .games
-        .lock()
-        .unwrap()
-        .insert(Uuid::new_v4(), Game::new(player_1, player_2, time_control));
+/// Creates a game with the creator seated with the colour of the preference, and answers with
+/// the URL of the page the opponent can open to take the other seat.
+async fn create_game(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    headers: HeaderMap,
+    Json(request): Json<NewGameRequest>,
+) -> Response {
+    let time_control = match TimeControl::from_notation(&request.time_control) {
+        Ok(time_control) => time_control,
+        Err(error) => return error_response(StatusCode::BAD_REQUEST, error),
+    };
+    let user = match get_anon_user(&session).await {
+        Ok(user) => user,
+        Err(error) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, error),
+    };
+    let colour = request.colour.to_player();
+    let game = Game::new(user, colour, time_control);
+    let game_id = game.get_id();
+    state.get_games().insert(game_id, game);
+
+    // The page of the game joins it for the opponent. The origin of the browser that created the
+    // game knows the scheme and host the platform is reached with, without it the URL is relative.
+    let join_path = format!("/games/{game_id}");
+    let join_url = match headers
+        .get(header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+    {
+        Some(origin) => format!("{origin}{join_path}"),
+        None => join_path,
+    };
+    let body = json!({
+        "game_id": game_id,
+        "colour": Colour::from(colour),
+        "join_url": join_url,
+    });
+    (StatusCode::CREATED, Json(body)).into_response()
+}
+
+/// Seats the caller in the open seat of the game, which starts the game.
+async fn join_game(
+    State(state): State<Arc<AppState>>,
+    session: Session,
+    Path(game_id): Path<Uuid>,
+) -> Response {
+    let user = match get_anon_user(&session).await {
+        Ok(user) => user,
+        Err(error) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, error),
+    };
+    let mut games = state.get_games();
+    let Some(game) = games.get_mut(&game_id) else {
+        return error_response(StatusCode::NOT_FOUND, format!("There is no game {game_id}"));
+    };
+    let was_started = game.is_started();
+    match game.join(user) {
+        Ok(colour) => {
+            if !was_started && game.is_started() {
+                run_clock(state.clone(), game_id);
+                for seat in [Player::White, Player::Black] {
+                    if !game.is_connected(seat) {
+                        schedule_abandon_check(&state, game_id, seat);
+                    }
+                }
+            }
+            Json(json!({ "game_id": game_id, "colour": Colour::from(colour) })).into_response()
+        }
+        Err(error) => error_response(StatusCode::CONFLICT, error),
+    }
+}
+
+/// Runs the clocks of the game on the server until the game is finished, so a player that runs
+/// out of time loses even when it never sends another message. The clocks are sent to the
+/// clients every `CLOCK_SYNC_INTERVAL`, and the task wakes up early when the active player runs
+/// out of time or an action switches the clock.
+fn run_clock(state: Arc<AppState>, game_id: Uuid) {
+    tokio::spawn(async move {
+        loop {
+            let (wait, clock_changed) = {
+                let mut games = state.get_games();
+                let Some(game) = games.get_mut(&game_id) else {
+                    return;
+                };
+                (game.update_clocks(), game.get_clock_changed())
+            };
+            let Some(wait) = wait else {
+                return;
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => (),
+                _ = clock_changed.notified() => (),
+            }
+        }
+    });
+}
+
+fn error_response(status: StatusCode, error: impl ToString) -> Response {
+    (status, Json(json!({ "error": error.to_string() }))).into_response()
 }
 
 async fn websocket_start(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    println!("{:?}", headers);
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    session: Session,
+) -> Response {
+    // The identity is read before the upgrade, the session cookie only comes with the request.
+    match get_anon_user(&session).await {
+        Ok(user) => ws.on_upgrade(|socket| handle_socket(socket, state, user)),
+        Err(error) => error_response(StatusCode::INTERNAL_SERVER_ERROR, error),
+    }
 }
 
-async fn handle_socket(socket: WebSocket, _state: Arc<AppState>) {
+/// Speaks the protocol of `quoridor_platform::protocol` with a single client. Once the client
+/// joined a game, every update of the game is forwarded to the socket.
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>, user: AnonUser) {
     let (mut sender, mut receiver) = socket.split();
+    let mut connection = Connection {
+        user,
+        game_id: None,
+        seat: None,
+        updates: None,
+    };
 
-    loop {
-        if let Some(Ok(Message::Text(msg))) = receiver.next().await {
-            sender
-                .send(Message::from(format!("Hello {:?}", msg)))
-                .await
-                .expect("Can't send response");
+    'socket: loop {
+        let replies = tokio::select! {
+            message = receiver.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    handle_message(&state, &mut connection, &text, Instant::now())
+                }
+                Some(Ok(Message::Binary(_))) => {
+                    vec![ServerMessage::error("Messages need to be sent as text")]
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => Vec::new(),
+            },
+            update = next_update(&mut connection.updates) => match update {
+                Ok(update) => vec![update],
+                // The socket fell behind, the current state replaces the updates it missed.
+                Err(RecvError::Lagged(_)) => current_state(&state, &connection).into_iter().collect(),
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        for reply in replies {
+            if sender.send(Message::Text(reply.to_json())).await.is_err() {
+                break 'socket;
+            }
         }
     }
+    leave_game(&state, &mut connection);
+}
+
+/// The player behind a socket and the game it joined.
+struct Connection {
+    user: AnonUser,
+    game_id: Option<Uuid>,
+    /// The seat of the player in the joined game, None for spectators.
+    seat: Option<Player>,
+    updates: Option<broadcast::Receiver<ServerMessage>>,
+}
+
+/// Handles a message of the client, returning the messages that only go back to the client.
+/// The updates of an executed action reach the client through the subscription to the game.
+fn handle_message(
+    state: &Arc<AppState>,
+    connection: &mut Connection,
+    text: &str,
+    received_at: Instant,
+) -> Vec<ServerMessage> {
+    let message = match ClientMessage::from_json(text) {
+        Ok(message) => message,
+        Err(error) => return vec![ServerMessage::error(error)],
+    };
+
+    if let ClientMessage::Join { .. } = message {
+        leave_game(state, connection);
+    }
+    let mut games = state.get_games();
+    match message {
+        ClientMessage::Join { game_id } => {
+            let Some(game) = games.get_mut(&game_id) else {
+                return vec![ServerMessage::error(format!("There is no game {game_id}"))];
+            };
+            // Subscribing while holding the lock makes sure no update is missed between the
+            // state and the subscription. A reconnecting player catches up with the state.
+            let seat = game.connect(connection.user.get_id());
+            connection.game_id = Some(game_id);
+            connection.seat = seat;
+            connection.updates = Some(game.subscribe());
+            vec![
+                ServerMessage::Joined {
+                    game_id,
+                    seat: seat.map(Colour::from),
+                },
+                game.get_state_update(),
+            ]
+        }
+        ClientMessage::Action { notation } => {
+            let Some(game_id) = connection.game_id else {
+                return vec![ServerMessage::error("Join a game before sending actions")];
+            };
+            let Some(game) = games.get_mut(&game_id) else {
+                return vec![ServerMessage::error(format!("There is no game {game_id}"))];
+            };
+            match game.execute_action(connection.user.get_id(), &notation, received_at) {
+                Ok(_) => Vec::new(),
+                Err(error) => vec![ServerMessage::error(error)],
+            }
+        }
+    }
+}
+
+/// Disconnects the socket from the game it joined. When the player has no other socket to the
+/// game, the player abandons the game unless it comes back within the grace period.
+fn leave_game(state: &Arc<AppState>, connection: &mut Connection) {
+    connection.updates = None;
+    let (Some(game_id), Some(seat)) = (connection.game_id.take(), connection.seat.take()) else {
+        return;
+    };
+    let mut games = state.get_games();
+    let Some(game) = games.get_mut(&game_id) else {
+        return;
+    };
+    if game.disconnect(seat) {
+        schedule_abandon_check(state, game_id, seat);
+    }
+}
+
+/// Ends the game as abandoned by the player in the seat when the player is still disconnected
+/// after the grace period.
+fn schedule_abandon_check(state: &Arc<AppState>, game_id: Uuid, seat: Player) {
+    let state = state.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(state.grace_period).await;
+        if let Some(game) = state.get_games().get_mut(&game_id) {
+            game.abandon_if_disconnected(seat, state.grace_period);
+        }
+    });
+}
+
+fn current_state(state: &AppState, connection: &Connection) -> Option<ServerMessage> {
+    let games = state.get_games();
+    connection
+        .game_id
+        .and_then(|game_id| games.get(&game_id))
+        .map(Game::get_state_update)
+}
+
+/// Waits for the next update of the joined game, or forever when no game is joined yet.
+async fn next_update(
+    updates: &mut Option<broadcast::Receiver<ServerMessage>>,
+) -> Result<ServerMessage, RecvError> {
+    match updates {
+        Some(updates) => updates.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest};
+
+    use super::*;
+
+    /// Serves the platform on a free port.
+    async fn serve() -> SocketAddr {
+        let state = Arc::new(AppState {
+            games: Mutex::new(HashMap::new()),
+            grace_period: Duration::from_secs(60),
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app(state).into_make_service())
+                .await
+                .unwrap();
+        });
+        address
+    }
+
+    /// Sends a request like a browser would, with the session cookie when it has one, and
+    /// returns the whole response.
+    async fn request(
+        address: SocketAddr,
+        method: &str,
+        path: &str,
+        cookie: Option<&str>,
+        body: &str,
+    ) -> String {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let cookie = cookie
+            .map(|cookie| format!("Cookie: {cookie}\r\n"))
+            .unwrap_or_default();
+        let request = format!(
+            "{method} {path} HTTP/1.1\r\nHost: {address}\r\n{cookie}\
+            Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    fn get_cookie(response: &str) -> String {
+        let header = response
+            .lines()
+            .find_map(|line| line.strip_prefix("set-cookie: "))
+            .expect("The response sets the session cookie");
+        String::from(header.split(';').next().unwrap())
+    }
+
+    fn get_json(response: &str) -> serde_json::Value {
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn page_and_socket_of_a_fresh_browser_share_the_seat() {
+        let address = serve().await;
+        let created = request(
+            address,
+            "POST",
+            "/api/v1/games",
+            None,
+            r#"{"time_control":"300+5","colour":"white"}"#,
+        )
+        .await;
+        let game_id = get_json(&created)["game_id"].as_str().unwrap().to_owned();
+
+        // The page of the game gets the identity before it joins and opens the socket.
+        let page = request(address, "GET", &format!("/games/{game_id}"), None, "").await;
+        let cookie = get_cookie(&page);
+        let joined = request(
+            address,
+            "POST",
+            &format!("/api/v1/games/{game_id}/join"),
+            Some(&cookie),
+            "",
+        )
+        .await;
+        assert_eq!(get_json(&joined)["colour"], "black");
+
+        let mut socket_request = format!("ws://{address}/ws").into_client_request().unwrap();
+        socket_request
+            .headers_mut()
+            .insert(header::COOKIE, cookie.parse().unwrap());
+        let (mut socket, _) = tokio_tungstenite::connect_async(socket_request)
+            .await
+            .unwrap();
+        let join = format!(r#"{{"version":1,"type":"join","game_id":"{game_id}"}}"#);
+        socket.send(tungstenite::Message::Text(join)).await.unwrap();
+        let reply = socket.next().await.unwrap().unwrap().into_text().unwrap();
+        let reply: serde_json::Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["type"], "joined");
+        assert_eq!(reply["seat"], "black");
+    }
 }
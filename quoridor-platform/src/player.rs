@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 // TODO Define a trait to handle playing a game with a session and use that for all the player
@@ -10,8 +11,8 @@ pub trait PlayerInfo {
     fn get_rating(&self) -> u16;
 }
 
-pub type PlayerInfo 
-
+/// A player without an account, the identity lives as long as the session of the browser.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AnonUser {
     id: Uuid,
     rating: u16,
@@ -25,6 +26,13 @@ impl AnonUser {
         }
     }
 }
+
+impl Default for AnonUser {
+    fn default() -> Self {
+        AnonUser::new()
+    }
+}
+
 impl PlayerInfo for AnonUser {
     fn get_id(&self) -> &Uuid {
         &self.id
@@ -35,6 +43,7 @@ impl PlayerInfo for AnonUser {
     }
 }
 
+#[allow(dead_code)]
 pub struct User {
     id: Uuid,
     name: String,
@@ -43,6 +52,7 @@ pub struct User {
     password: String,
 }
 
+#[allow(dead_code)]
 pub struct Engine {
     id: Uuid,
     name: String,
@@ -0,0 +1,311 @@
+//! The JSON messages sent over the `/ws` websocket. Every message carries the version of the
+//! protocol and a `type` field naming the message:
+//!
+//! ```text
+//! {"version":1,"type":"join","game_id":"67e55044-10b1-426f-9247-bb680e5fe0c8"}
+//! {"version":1,"type":"action","notation":"E2"}
+//! ```
+//!
+//! The server answers with the state of the game after every change, or with an error that is
+//! only sent to the client that caused it.
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::Player;
+use quoridor_core::gamestate::{GameStatus, Gamestate, VictoryReason};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The version of the protocol the server speaks, messages with another version are refused.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A message from a client, wrapped with the version of the protocol.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClientEnvelope {
+    pub version: u32,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Binds the socket to the game, the client receives the state of the game and every update
+    /// from then on.
+    Join { game_id: Uuid },
+    /// Plays the action in the notation of `Action::from_notation` in the joined game.
+    Action { notation: String },
+}
+
+impl ClientMessage {
+    /// Reads a message from the text of the websocket, checking the version of the protocol.
+    pub fn from_json(json: &str) -> Result<ClientMessage, String> {
+        let envelope: ClientEnvelope =
+            serde_json::from_str(json).map_err(|error| format!("Invalid message: {error}"))?;
+        if envelope.version != PROTOCOL_VERSION {
+            return Err(format!(
+                "Unsupported protocol version {}, the server speaks version {PROTOCOL_VERSION}",
+                envelope.version
+            ));
+        }
+        Ok(envelope.message)
+    }
+}
+
+/// A message from the server, wrapped with the version of the protocol.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ServerEnvelope {
+    pub version: u32,
+    #[serde(flatten)]
+    pub message: ServerMessage,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The answer to a join, with the colour the client plays with. Spectators have no seat.
+    Joined {
+        game_id: Uuid,
+        seat: Option<Colour>,
+    },
+    State(StateUpdate),
+    /// The remaining time of both players in milliseconds, sent while the clock runs.
+    Clock {
+        clocks: PerPlayer<u64>,
+        active_player: Colour,
+    },
+    /// The player in the seat connected to the game or lost its last connection.
+    Connection {
+        colour: Colour,
+        connected: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl ServerMessage {
+    pub fn error(message: impl ToString) -> ServerMessage {
+        ServerMessage::Error {
+            message: message.to_string(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&ServerEnvelope {
+            version: PROTOCOL_VERSION,
+            message: self.clone(),
+        })
+        .expect("Server messages serialize to JSON")
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Colour {
+    White,
+    Black,
+}
+
+impl From<Player> for Colour {
+    fn from(player: Player) -> Self {
+        match player {
+            Player::White => Colour::White,
+            Player::Black => Colour::Black,
+        }
+    }
+}
+
+impl From<Colour> for Player {
+    fn from(colour: Colour) -> Self {
+        match colour {
+            Colour::White => Player::White,
+            Colour::Black => Player::Black,
+        }
+    }
+}
+
+/// A value for each player.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PerPlayer<T> {
+    pub white: T,
+    pub black: T,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum StatusUpdate {
+    /// Only the creator of the game is seated, the game starts when an opponent joins.
+    WaitingForOpponent,
+    InProgress,
+    Finished {
+        winner: Colour,
+        reason: String,
+    },
+}
+
+impl From<GameStatus> for StatusUpdate {
+    fn from(status: GameStatus) -> Self {
+        match status {
+            GameStatus::InProgress => StatusUpdate::InProgress,
+            GameStatus::Finished { won_by, reason } => StatusUpdate::Finished {
+                winner: Colour::from(won_by),
+                reason: String::from(match reason {
+                    VictoryReason::ReachedOppositeSide => "reached_opposite_side",
+                    VictoryReason::Resigned => "resigned",
+                    VictoryReason::OutOffTime => "out_of_time",
+                    VictoryReason::Abandoned => "abandoned",
+                }),
+            },
+        }
+    }
+}
+
+/// Everything a client needs to show the game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StateUpdate {
+    pub game_id: Uuid,
+    /// The board in the notation of `Boardstate::get_position_notation`.
+    pub board: String,
+    pub active_player: Colour,
+    pub moves: Vec<String>,
+    pub last_move: Option<String>,
+    pub walls_left: PerPlayer<u8>,
+    /// The remaining time of both players in milliseconds, None for games without a clock.
+    pub clocks: Option<PerPlayer<u64>>,
+    pub status: StatusUpdate,
+    /// Whether the players have the game open, the gamestate doesn't know this so it is false
+    /// until the game fills it in.
+    pub connected: PerPlayer<bool>,
+}
+
+impl StateUpdate {
+    pub fn from_gamestate(game_id: Uuid, gamestate: &Gamestate) -> StateUpdate {
+        let boardstate = gamestate.get_boardstate();
+        let moves: Vec<String> = gamestate
+            .get_moves()
+            .iter()
+            .map(|executed_action| executed_action.get_action())
+            .filter(|action| !matches!(action, Action::Resigned(_)))
+            .map(|action| action.get_notation())
+            .collect();
+        let remaining_time = |player| {
+            gamestate
+                .get_remaining_time(player)
+                .map(|time| time.as_millis() as u64)
+        };
+        let clocks = match (remaining_time(Player::White), remaining_time(Player::Black)) {
+            (Some(white), Some(black)) => Some(PerPlayer { white, black }),
+            _ => None,
+        };
+
+        StateUpdate {
+            game_id,
+            board: boardstate.get_position_notation(),
+            active_player: Colour::from(boardstate.get_active_player()),
+            last_move: moves.last().cloned(),
+            moves,
+            walls_left: PerPlayer {
+                white: boardstate.get_available_walls_white_player(),
+                black: boardstate.get_available_walls_black_player(),
+            },
+            clocks,
+            status: StatusUpdate::from(gamestate.get_status()),
+            connected: PerPlayer {
+                white: false,
+                black: false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quoridor_core::gamestate::TimeControl;
+
+    use super::*;
+
+    #[test]
+    fn client_messages_are_read() {
+        let game_id = Uuid::new_v4();
+        assert_eq!(
+            ClientMessage::from_json(&format!(
+                r#"{{"version":1,"type":"join","game_id":"{game_id}"}}"#
+            )),
+            Ok(ClientMessage::Join { game_id })
+        );
+        assert_eq!(
+            ClientMessage::from_json(r#"{"version":1,"type":"action","notation":"E2"}"#),
+            Ok(ClientMessage::Action {
+                notation: String::from("E2")
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_client_messages_are_refused() {
+        let inputs = [
+            r#"{"version":2,"type":"action","notation":"E2"}"#,
+            r#"{"type":"action","notation":"E2"}"#,
+            r#"{"version":1,"type":"dance"}"#,
+            r#"{"version":1,"type":"join","game_id":"not-a-uuid"}"#,
+            "Hello",
+        ];
+        for input in inputs {
+            assert!(ClientMessage::from_json(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn state_update_describes_the_game() {
+        let mut gamestate = Gamestate::new(TimeControl::Timed {
+            seconds: 60,
+            increment: 0,
+        });
+        for notation in ["E2", "D7h"] {
+            gamestate
+                .execute_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+        let game_id = Uuid::new_v4();
+        let update = StateUpdate::from_gamestate(game_id, &gamestate);
+
+        assert_eq!(update.board, "E2 E9 D7h 10 9 w");
+        assert_eq!(update.active_player, Colour::White);
+        assert_eq!(update.last_move, Some(String::from("D7h")));
+        assert_eq!(
+            update.walls_left,
+            PerPlayer {
+                white: 10,
+                black: 9
+            }
+        );
+        assert!(update.clocks.unwrap().white <= 60_000);
+        assert_eq!(update.status, StatusUpdate::InProgress);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&ServerMessage::State(update).to_json()).unwrap();
+        assert_eq!(json["version"], PROTOCOL_VERSION);
+        assert_eq!(json["type"], "state");
+        assert_eq!(json["game_id"], game_id.to_string());
+        assert_eq!(json["status"]["state"], "in_progress");
+    }
+
+    #[test]
+    fn finished_games_have_a_winner() {
+        let mut gamestate = Gamestate::new(TimeControl::Unlimited);
+        gamestate.resign(Player::White).unwrap();
+        let update = StateUpdate::from_gamestate(Uuid::new_v4(), &gamestate);
+        assert_eq!(update.clocks, None);
+        assert_eq!(
+            update.status,
+            StatusUpdate::Finished {
+                winner: Colour::Black,
+                reason: String::from("resigned")
+            }
+        );
+        assert_eq!(
+            ServerMessage::error("Bad").to_json(),
+            r#"{"version":1,"type":"error","message":"Bad"}"#
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! The identity of anonymous players, stored in the session of the browser so the same person is
+//! recognised across page reloads and reconnects.
+
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tower_sessions::cookie::time::Duration;
+use tower_sessions::session::Error;
+use tower_sessions::{Expiry, MemoryStore, Session, SessionManagerLayer};
+
+use crate::player::AnonUser;
+
+/// The key of the anonymous identity in the session.
+pub const ANON_USER_KEY: &str = "anon_user";
+
+/// The number of days a session without any requests is kept.
+const SESSION_INACTIVITY_DAYS: i64 = 30;
+
+/// The layer that attaches a session cookie to every response. The sessions are kept in memory,
+/// so all identities are forgotten when the server restarts, like the games themselves.
+pub fn session_layer() -> SessionManagerLayer<MemoryStore> {
+    SessionManagerLayer::new(MemoryStore::default())
+        .with_secure(false)
+        .with_expiry(Expiry::OnInactivity(Duration::days(
+            SESSION_INACTIVITY_DAYS,
+        )))
+}
+
+/// The anonymous identity of the session, a new identity is created and stored on the first
+/// request of a browser.
+pub async fn get_anon_user(session: &Session) -> Result<AnonUser, Error> {
+    if let Some(user) = session.get::<AnonUser>(ANON_USER_KEY).await? {
+        return Ok(user);
+    }
+    let user = AnonUser::new();
+    session.insert(ANON_USER_KEY, &user).await?;
+    Ok(user)
+}
+
+/// Creates the anonymous identity before the request reaches its handler, also for the requests
+/// of pages. A page that sends several requests at once, like joining a game and opening the
+/// websocket, then sends them all with the cookie of the same identity instead of every request
+/// creating an identity of its own. Needs to run inside the `session_layer`.
+pub async fn ensure_anon_user(session: Session, request: Request, next: Next) -> Response {
+    if let Err(error) = get_anon_user(&session).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::player::PlayerInfo;
+
+    #[tokio::test]
+    async fn identity_is_kept_in_the_session() {
+        let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        let user = get_anon_user(&session).await.unwrap();
+        assert_eq!(get_anon_user(&session).await.unwrap(), user);
+
+        let other_session = Session::new(None, Arc::new(MemoryStore::default()), None);
+        let other_user = get_anon_user(&other_session).await.unwrap();
+        assert_ne!(other_user.get_id(), user.get_id());
+    }
+}
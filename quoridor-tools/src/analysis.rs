@@ -0,0 +1,314 @@
+//! Analyses finished games: every position is searched by the engine to see how much each move
+//! lost compared to the best move.
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::{Boardstate, Player};
+use quoridor_core::error::Result;
+use quoridor_core::gamestate::GameStatus;
+use quoridor_core::record::GameRecord;
+use quoridor_engine::evaluation::{Evaluator, WIN_SCORE, WIN_THRESHOLD};
+use quoridor_engine::search::{Engine, SearchLimit};
+
+/// How bad a move was, by how much it dropped the evaluation of the player that made it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoveLabel {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveLabel {
+    pub fn get_notation(&self) -> &'static str {
+        match self {
+            MoveLabel::Inaccuracy => "inaccuracy",
+            MoveLabel::Mistake => "mistake",
+            MoveLabel::Blunder => "blunder",
+        }
+    }
+}
+
+/// The search limit for every position and the evaluation drops that earn a label. The default
+/// evaluation gives 100 for every step a pawn is ahead, so the default thresholds are half a
+/// step, a step and two steps.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AnalysisConfig {
+    pub limit: SearchLimit,
+    pub inaccuracy: i32,
+    pub mistake: i32,
+    pub blunder: i32,
+}
+
+impl Default for AnalysisConfig {
+    fn default() -> Self {
+        AnalysisConfig {
+            limit: SearchLimit::Depth(3),
+            inaccuracy: 50,
+            mistake: 100,
+            blunder: 200,
+        }
+    }
+}
+
+impl AnalysisConfig {
+    /// The label of a move that lost the amount of evaluation, None for a good move.
+    pub fn get_label(&self, loss: i32) -> Option<MoveLabel> {
+        if loss >= self.blunder {
+            Some(MoveLabel::Blunder)
+        } else if loss >= self.mistake {
+            Some(MoveLabel::Mistake)
+        } else if loss >= self.inaccuracy {
+            Some(MoveLabel::Inaccuracy)
+        } else {
+            None
+        }
+    }
+}
+
+/// The analysis of a single move. The evaluations are from the point of view of the player that
+/// made the move.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveAnalysis {
+    ply: usize,
+    player: Player,
+    action: Action,
+    best_action: Action,
+    evaluation_before: i32,
+    evaluation_after: i32,
+    label: Option<MoveLabel>,
+}
+
+impl MoveAnalysis {
+    /// The ply of the move, counting from 0.
+    pub fn get_ply(&self) -> usize {
+        self.ply
+    }
+
+    pub fn get_player(&self) -> Player {
+        self.player
+    }
+
+    /// The action that was played.
+    pub fn get_action(&self) -> Action {
+        self.action
+    }
+
+    /// The action the engine prefers in the position.
+    pub fn get_best_action(&self) -> Action {
+        self.best_action
+    }
+
+    /// The evaluation of the position before the move, which is the evaluation of the best
+    /// action.
+    pub fn get_evaluation_before(&self) -> i32 {
+        self.evaluation_before
+    }
+
+    pub fn get_evaluation_after(&self) -> i32 {
+        self.evaluation_after
+    }
+
+    /// How much the move dropped the evaluation, 0 when the move was as good as the best action.
+    pub fn get_loss(&self) -> i32 {
+        (self.evaluation_before - self.evaluation_after).max(0)
+    }
+
+    pub fn get_label(&self) -> Option<MoveLabel> {
+        self.label
+    }
+
+    /// A short description of the move for the comment of an annotated record, for example
+    /// `+1.00 -> -1.50, blunder, best D3h`.
+    pub fn get_comment(&self) -> String {
+        let mut comment = format!(
+            "{} -> {}",
+            evaluation_to_notation(self.evaluation_before),
+            evaluation_to_notation(self.evaluation_after)
+        );
+        if let Some(label) = self.label {
+            comment.push_str(", ");
+            comment.push_str(label.get_notation());
+        }
+        if self.best_action != self.action {
+            comment.push_str(", best ");
+            comment.push_str(&self.best_action.get_notation());
+        }
+        comment
+    }
+}
+
+/// The analysis of all the moves of a game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameAnalysis {
+    moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    pub fn get_moves(&self) -> &Vec<MoveAnalysis> {
+        &self.moves
+    }
+
+    /// A copy of the record with the comment of the analysis after every move.
+    pub fn annotate(&self, record: &GameRecord) -> GameRecord {
+        let mut annotated = record.clone();
+        for analysis in &self.moves {
+            annotated.set_comment(analysis.ply, &analysis.get_comment());
+        }
+        annotated
+    }
+}
+
+/// Searches every position of the game with the engine. The played move is evaluated one ply
+/// shallower than the position itself, so it gets the same depth as the best action and the two
+/// are compared fairly.
+pub fn analyse_game<E: Evaluator>(
+    record: &GameRecord,
+    engine: &mut Engine<E>,
+    config: &AnalysisConfig,
+) -> Result<GameAnalysis> {
+    let gamestate = record.to_gamestate()?;
+    let actions: Vec<Action> = gamestate
+        .get_moves()
+        .iter()
+        .map(|executed_action| executed_action.get_action())
+        .filter(|action| !matches!(action, Action::Resigned(_)))
+        .collect();
+
+    let mut boardstate = Boardstate::new();
+    let mut moves = Vec::with_capacity(actions.len());
+    for (ply, &action) in actions.iter().enumerate() {
+        let player = boardstate.get_active_player();
+        let Some(search) = engine.search(&boardstate, config.limit) else {
+            break;
+        };
+        let status = boardstate.apply_action(action)?;
+
+        let evaluation_before = search.get_score();
+        let evaluation_after = match status {
+            GameStatus::Finished { .. } => WIN_SCORE,
+            GameStatus::InProgress if action == search.get_best_action() => evaluation_before,
+            GameStatus::InProgress => -evaluate_reply(engine, &boardstate, config.limit),
+        };
+        moves.push(MoveAnalysis {
+            ply,
+            player,
+            action,
+            best_action: search.get_best_action(),
+            evaluation_before,
+            evaluation_after,
+            label: config.get_label(evaluation_before - evaluation_after),
+        });
+    }
+
+    Ok(GameAnalysis { moves })
+}
+
+/// The evaluation of the position after a move for the player to reply, searched one ply less
+/// than the limit. At the lowest depth that is the static evaluation.
+fn evaluate_reply<E: Evaluator>(
+    engine: &mut Engine<E>,
+    boardstate: &Boardstate,
+    limit: SearchLimit,
+) -> i32 {
+    let limit = match limit {
+        SearchLimit::Depth(depth) if depth > 1 => SearchLimit::Depth(depth - 1),
+        SearchLimit::Depth(_) => return engine.get_evaluator().evaluate(boardstate),
+        SearchLimit::Time(_) | SearchLimit::Infinite => limit,
+    };
+    match engine.search(boardstate, limit) {
+        Some(result) => result.get_score(),
+        None => engine.get_evaluator().evaluate(boardstate),
+    }
+}
+
+/// Writes an evaluation in steps, `+1.50` for a player one and a half step ahead. A forced win
+/// is written as `#3` for a win in three plies, a forced loss as `#-3`.
+pub fn evaluation_to_notation(evaluation: i32) -> String {
+    if evaluation > WIN_THRESHOLD {
+        format!("#{}", WIN_SCORE - evaluation)
+    } else if evaluation < -WIN_THRESHOLD {
+        format!("#-{}", WIN_SCORE + evaluation)
+    } else {
+        format!("{:+.2}", f64::from(evaluation) / 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(moves: &str) -> GameRecord {
+        GameRecord::from_record_notation(&format!("{moves} *")).unwrap()
+    }
+
+    fn config() -> AnalysisConfig {
+        AnalysisConfig {
+            limit: SearchLimit::Depth(1),
+            ..AnalysisConfig::default()
+        }
+    }
+
+    #[test]
+    fn labels_follow_the_thresholds() {
+        let config = AnalysisConfig::default();
+        assert_eq!(config.get_label(0), None);
+        assert_eq!(config.get_label(49), None);
+        assert_eq!(config.get_label(50), Some(MoveLabel::Inaccuracy));
+        assert_eq!(config.get_label(150), Some(MoveLabel::Mistake));
+        assert_eq!(config.get_label(WIN_SCORE), Some(MoveLabel::Blunder));
+    }
+
+    #[test]
+    fn evaluations_are_written_in_steps() {
+        assert_eq!(evaluation_to_notation(150), "+1.50");
+        assert_eq!(evaluation_to_notation(-25), "-0.25");
+        assert_eq!(evaluation_to_notation(0), "+0.00");
+        assert_eq!(evaluation_to_notation(WIN_SCORE - 3), "#3");
+        assert_eq!(evaluation_to_notation(-WIN_SCORE + 2), "#-2");
+    }
+
+    #[test]
+    fn walking_backwards_is_a_mistake() {
+        let record = record("1. E2 E8 2. E1");
+        let analysis = analyse_game(&record, &mut Engine::new(), &config()).unwrap();
+        let moves = analysis.get_moves();
+        assert_eq!(moves.len(), 3);
+
+        assert_eq!(moves[0].get_player(), Player::White);
+        assert_eq!(moves[0].get_label(), None);
+        assert_eq!(moves[2].get_player(), Player::White);
+        assert_eq!(moves[2].get_action(), Action::from_notation("E1").unwrap());
+        assert_ne!(moves[2].get_best_action(), moves[2].get_action());
+        assert!(moves[2].get_loss() >= 100);
+        assert!(moves[2].get_label() >= Some(MoveLabel::Mistake));
+    }
+
+    #[test]
+    fn annotated_record_has_the_comments() {
+        let record = record("1. E2 E8 2. E1");
+        let analysis = analyse_game(&record, &mut Engine::new(), &config()).unwrap();
+        let annotated = analysis.annotate(&record);
+        assert_eq!(annotated.get_actions(), record.get_actions());
+        assert_eq!(
+            annotated.get_comment(2),
+            Some(analysis.get_moves()[2].get_comment().as_str())
+        );
+        assert!(annotated.get_comment(2).unwrap().contains("best"));
+
+        let parsed = GameRecord::from_record_notation(&annotated.get_record_notation()).unwrap();
+        assert_eq!(parsed, annotated);
+    }
+
+    #[test]
+    fn the_winning_move_is_a_forced_win() {
+        let record = GameRecord::from_record_notation(
+            "[Result \"0-1\"]\n[Reason \"ReachedOppositeSide\"]\n\n1. E2 E8 2. E3 E7 3. E4 E6 \
+             4. E5 E4 5. E6 E3 6. E7 E2 7. E8 E1 0-1",
+        )
+        .unwrap();
+        let analysis = analyse_game(&record, &mut Engine::new(), &config()).unwrap();
+        let last = analysis.get_moves().last().unwrap();
+        assert_eq!(last.get_player(), Player::Black);
+        assert_eq!(last.get_evaluation_after(), WIN_SCORE);
+        assert_eq!(last.get_label(), None);
+    }
+}
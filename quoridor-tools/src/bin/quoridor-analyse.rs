@@ -0,0 +1,98 @@
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use quoridor_core::record::GameRecord;
+use quoridor_engine::search::{Engine, SearchLimit};
+use quoridor_tools::analysis::{analyse_game, evaluation_to_notation, AnalysisConfig};
+use quoridor_tools::statistics::game_statistics;
+
+const USAGE: &str = "Usage: quoridor-analyse <record file> [--depth N | --movetime MS] \
+[--output FILE] [--statistics FILE]
+
+Searches every position of the game and prints how much every move lost compared to the best
+move. The annotated record is written to the output file, or printed when there is none. The
+statistics of both players are written as JSON to the statistics file, a record doesn't store
+the time of the moves so the times are zero.";
+
+/// Analyses a saved game and writes the annotated record.
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(arguments: Vec<String>) -> Result<(), String> {
+    let mut record_path = None;
+    let mut output = None;
+    let mut statistics = None;
+    let mut config = AnalysisConfig::default();
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {argument}"))
+        };
+        match argument.as_str() {
+            "--depth" => config.limit = SearchLimit::Depth(parse(&value()?)?),
+            "--movetime" => {
+                config.limit = SearchLimit::Time(Duration::from_millis(parse(&value()?)?))
+            }
+            "--output" => output = Some(value()?),
+            "--statistics" => statistics = Some(value()?),
+            _ if argument.starts_with("--") => return Err(format!("Unknown option {argument}")),
+            _ if record_path.is_none() => record_path = Some(argument),
+            _ => return Err(format!("Unexpected argument {argument}")),
+        }
+    }
+    let record_path = record_path.ok_or_else(|| String::from("Missing the record file"))?;
+
+    let notation = fs::read_to_string(&record_path)
+        .map_err(|error| format!("Could not read {record_path}: {error}"))?;
+    let record = GameRecord::from_record_notation(&notation).map_err(|error| error.to_string())?;
+    let analysis =
+        analyse_game(&record, &mut Engine::new(), &config).map_err(|error| error.to_string())?;
+
+    for analysis in analysis.get_moves() {
+        let label = analysis
+            .get_label()
+            .map_or("", |label| label.get_notation());
+        println!(
+            "{:>4}. {:<4} {:>7} -> {:>7}  best {:<4} {label}",
+            analysis.get_ply() / 2 + 1,
+            analysis.get_action().get_notation(),
+            evaluation_to_notation(analysis.get_evaluation_before()),
+            evaluation_to_notation(analysis.get_evaluation_after()),
+            analysis.get_best_action().get_notation(),
+        );
+    }
+
+    if let Some(path) = statistics {
+        let gamestate = record.to_gamestate().map_err(|error| error.to_string())?;
+        let statistics =
+            game_statistics(&gamestate, &analysis).map_err(|error| error.to_string())?;
+        fs::write(&path, statistics.to_json())
+            .map_err(|error| format!("Could not write {path}: {error}"))?;
+    }
+
+    let annotated = analysis.annotate(&record).get_record_notation();
+    match output {
+        Some(path) => {
+            fs::write(&path, annotated).map_err(|error| format!("Could not write {path}: {error}"))
+        }
+        None => {
+            println!("\n{annotated}");
+            Ok(())
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number {value}"))
+}
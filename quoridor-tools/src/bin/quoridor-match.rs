@@ -0,0 +1,128 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use quoridor_core::gamestate::TimeControl;
+use quoridor_tools::elo::{Sprt, SprtStatus};
+use quoridor_tools::matches::{run_match, MatchConfig};
+use quoridor_tools::players::{player_from_spec, MatchPlayer, PlayerError};
+
+const USAGE: &str = "Usage: quoridor-match <engine> <engine> [--games N] [--time 60+1] \
+[--max-plies N] [--records FILE] [--sprt ELO0 ELO1]
+
+An engine is alphabeta[:depth], mcts[:iterations] or the path of a QEI engine executable.";
+
+/// The error rates of the SPRT, the usual 5% for both.
+const SPRT_ERROR_RATE: f64 = 0.05;
+
+/// Plays a match between two engines and reports the score and the Elo difference.
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(arguments: Vec<String>) -> Result<(), String> {
+    let mut engines = Vec::new();
+    let mut config = MatchConfig::default();
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {argument}"))
+        };
+        match argument.as_str() {
+            "--games" => config.games = parse(&value()?)?,
+            "--time" => {
+                config.time_control =
+                    TimeControl::from_notation(&value()?).map_err(|error| error.to_string())?
+            }
+            "--max-plies" => config.max_plies = parse(&value()?)?,
+            "--records" => config.records = Some(PathBuf::from(value()?)),
+            "--sprt" => {
+                let elo0 = parse(&value()?)?;
+                let elo1 = parse(&value()?)?;
+                config.sprt = Some(Sprt::new(elo0, elo1, SPRT_ERROR_RATE, SPRT_ERROR_RATE));
+            }
+            _ if argument.starts_with("--") => return Err(format!("Unknown option {argument}")),
+            _ => engines.push(argument),
+        }
+    }
+    let [first, second] = engines.as_slice() else {
+        return Err(String::from("Expected two engines"));
+    };
+
+    let mut first = create_player(first)?;
+    let mut second = create_player(second)?;
+    println!(
+        "{} vs {}, {} games at {}",
+        first.get_name(),
+        second.get_name(),
+        config.games,
+        config.time_control.get_notation()
+    );
+
+    let score = run_match(first.as_mut(), second.as_mut(), &config, |record, score| {
+        println!(
+            "Game {}: {} - {} {:?}, score {}-{}-{}",
+            score.get_games(),
+            record.get_white(),
+            record.get_black(),
+            record.get_status(),
+            score.wins,
+            score.losses,
+            score.draws
+        );
+    })
+    .map_err(|error| format!("Could not write the records: {error}"))?;
+
+    println!(
+        "\n{} vs {}: {} wins, {} losses, {} draws ({:.1}%)",
+        first.get_name(),
+        second.get_name(),
+        score.wins,
+        score.losses,
+        score.draws,
+        score.get_score() * 100.0
+    );
+    match score.get_elo() {
+        Some(elo) => println!(
+            "Elo difference: {:.1} +/- {:.1}",
+            elo.get_difference(),
+            elo.get_error_margin()
+        ),
+        None => println!("Elo difference: unknown, one engine scored all the points"),
+    }
+    if let Some(sprt) = config.sprt {
+        let (lower, upper) = sprt.get_bounds();
+        let status = match sprt.get_status(&score) {
+            SprtStatus::AcceptH0 => "H0 accepted",
+            SprtStatus::AcceptH1 => "H1 accepted",
+            SprtStatus::Continue => "undecided",
+        };
+        println!(
+            "SPRT: LLR {:.2} ({:.2}, {:.2}), {status}",
+            sprt.get_llr(&score),
+            lower,
+            upper
+        );
+    }
+
+    Ok(())
+}
+
+fn create_player(spec: &str) -> Result<Box<dyn MatchPlayer>, String> {
+    player_from_spec(spec).map_err(|error| match error {
+        PlayerError::InvalidSpec(_) => error.to_string(),
+        PlayerError::Client(error) => format!("Could not start {spec}: {error}"),
+    })
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number {value}"))
+}
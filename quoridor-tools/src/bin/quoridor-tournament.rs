@@ -0,0 +1,122 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use quoridor_core::gamestate::TimeControl;
+use quoridor_tools::players::{parse_spec, player_from_spec};
+use quoridor_tools::tournament::{Tournament, TournamentConfig, TournamentFormat};
+
+const USAGE: &str = "Usage: quoridor-tournament <state file> [--gauntlet] [--games N] \
+[--time 60+1] [--max-plies N] [--concurrency N] [--records FILE] <engine>...
+
+Starts a new tournament between the engines, or continues the tournament of the state file when
+no engines are given. An engine is alphabeta[:depth], mcts[:iterations] or the path of a QEI
+engine executable. With --gauntlet the first engine plays all the others, otherwise every engine
+plays every other engine.";
+
+/// Runs a tournament and prints the crosstable.
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}\n\n{USAGE}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(arguments: Vec<String>) -> Result<(), String> {
+    let mut arguments = arguments.into_iter();
+    let state_path = PathBuf::from(
+        arguments
+            .next()
+            .ok_or_else(|| String::from("Missing the state file"))?,
+    );
+
+    let mut config = TournamentConfig {
+        format: TournamentFormat::RoundRobin,
+        engines: Vec::new(),
+        games_per_pairing: 2,
+        time_control: TimeControl::Timed {
+            seconds: 60,
+            increment: 1,
+        },
+        max_plies: 400,
+        concurrency: 1,
+        records: None,
+    };
+    while let Some(argument) = arguments.next() {
+        let mut value = || {
+            arguments
+                .next()
+                .ok_or_else(|| format!("Missing value for {argument}"))
+        };
+        match argument.as_str() {
+            "--gauntlet" => config.format = TournamentFormat::Gauntlet,
+            "--games" => config.games_per_pairing = parse(&value()?)?,
+            "--time" => {
+                config.time_control =
+                    TimeControl::from_notation(&value()?).map_err(|error| error.to_string())?
+            }
+            "--max-plies" => config.max_plies = parse(&value()?)?,
+            "--concurrency" => config.concurrency = parse(&value()?)?,
+            "--records" => config.records = Some(PathBuf::from(value()?)),
+            _ if argument.starts_with("--") => return Err(format!("Unknown option {argument}")),
+            _ => config.engines.push(argument),
+        }
+    }
+
+    let mut tournament = load_or_create(&state_path, config)?;
+    let total = tournament.get_games().len();
+    let mut played = tournament
+        .get_games()
+        .iter()
+        .filter(|game| game.result.is_some())
+        .count();
+    tournament
+        .run(Some(&state_path), player_from_spec, |record| {
+            played += 1;
+            println!(
+                "Game {played}/{total}: {} - {} {:?}",
+                record.get_white(),
+                record.get_black(),
+                record.get_status()
+            );
+        })
+        .map_err(|error| format!("Could not save the tournament: {error}"))?;
+
+    println!("\n{}", tournament.get_crosstable().get_notation());
+    Ok(())
+}
+
+/// Continues the tournament of the state file, or starts a new one when engines are given.
+fn load_or_create(state_path: &Path, config: TournamentConfig) -> Result<Tournament, String> {
+    if config.engines.is_empty() {
+        return Tournament::load(state_path)
+            .map_err(|error| format!("Could not load {}: {error}", state_path.display()));
+    }
+    if state_path.exists() {
+        return Err(format!(
+            "{} already exists, leave out the engines to continue it",
+            state_path.display()
+        ));
+    }
+    if config.engines.len() < 2 {
+        return Err(String::from("A tournament needs at least two engines"));
+    }
+    // An engine that fails to start only loses its games, but a spec that can't be read is a
+    // mistake on the command line.
+    for engine in &config.engines {
+        parse_spec(engine).map_err(|error| error.to_string())?;
+    }
+
+    let tournament = Tournament::new(config);
+    tournament
+        .save(state_path)
+        .map_err(|error| format!("Could not save the tournament: {error}"))?;
+    Ok(tournament)
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number {value}"))
+}
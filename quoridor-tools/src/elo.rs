@@ -0,0 +1,254 @@
+//! Elo estimation and the sequential probability ratio test (SPRT) used to compare two engines.
+
+/// The z-score of a two-sided 95% confidence interval.
+const CONFIDENCE_Z: f64 = 1.959964;
+
+/// The difference in Elo between two players, with the margin of the 95% confidence interval.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EloEstimate {
+    difference: f64,
+    error_margin: f64,
+}
+
+impl EloEstimate {
+    /// How much stronger the first player is, negative when it is weaker.
+    pub fn get_difference(&self) -> f64 {
+        self.difference
+    }
+
+    /// The real difference is within this many Elo of the estimate with 95% confidence.
+    pub fn get_error_margin(&self) -> f64 {
+        self.error_margin
+    }
+}
+
+/// The results of a match from the point of view of the first player.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl MatchScore {
+    pub fn new(wins: u32, losses: u32, draws: u32) -> MatchScore {
+        MatchScore {
+            wins,
+            losses,
+            draws,
+        }
+    }
+
+    pub fn get_games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// The points per game, a win is worth one point and a draw half a point.
+    pub fn get_score(&self) -> f64 {
+        let games = self.get_games();
+        if games == 0 {
+            return 0.5;
+        }
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(games)
+    }
+
+    /// The variance of the points of a single game.
+    fn get_variance(&self) -> f64 {
+        let games = self.get_games();
+        if games == 0 {
+            return 0.0;
+        }
+        let score = self.get_score();
+        (f64::from(self.wins) * (1.0 - score).powi(2)
+            + f64::from(self.losses) * score.powi(2)
+            + f64::from(self.draws) * (0.5 - score).powi(2))
+            / f64::from(games)
+    }
+
+    /// Estimates the Elo difference from the score. None when no games were played or when one
+    /// player scored all the points, since the difference is unbounded then.
+    pub fn get_elo(&self) -> Option<EloEstimate> {
+        let score = self.get_score();
+        if self.get_games() == 0 || score <= 0.0 || score >= 1.0 {
+            return None;
+        }
+
+        let standard_error = (self.get_variance() / f64::from(self.get_games())).sqrt();
+        let lower = (score - CONFIDENCE_Z * standard_error).max(f64::EPSILON);
+        let upper = (score + CONFIDENCE_Z * standard_error).min(1.0 - f64::EPSILON);
+        Some(EloEstimate {
+            difference: score_to_elo(score),
+            error_margin: (score_to_elo(upper) - score_to_elo(lower)) / 2.0,
+        })
+    }
+}
+
+/// The Elo difference that gives the expected score.
+pub fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// The expected score of a player that is the Elo difference stronger.
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// The decision of a sequential probability ratio test.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SprtStatus {
+    /// The difference is at most `elo0`, the first player is not an improvement.
+    AcceptH0,
+    /// The difference is at least `elo1`, the first player is an improvement.
+    AcceptH1,
+    /// Not enough games were played to decide yet.
+    Continue,
+}
+
+/// A sequential probability ratio test between the hypotheses that the first player is `elo0`
+/// stronger (H0) or `elo1` stronger (H1). After every game the log likelihood ratio is compared
+/// to the bounds, so a match can stop as soon as the result is clear. `alpha` and `beta` are the
+/// chances of accepting H1 while H0 is true and the other way around.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprt {
+    elo0: f64,
+    elo1: f64,
+    alpha: f64,
+    beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64, alpha: f64, beta: f64) -> Sprt {
+        Sprt {
+            elo0,
+            elo1,
+            alpha,
+            beta,
+        }
+    }
+
+    /// The lower and upper bound of the log likelihood ratio, below the lower bound H0 is
+    /// accepted and above the upper bound H1.
+    pub fn get_bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// The log likelihood ratio of the score, using the normal approximation of the results.
+    pub fn get_llr(&self, score: &MatchScore) -> f64 {
+        if score.get_games() == 0 {
+            return 0.0;
+        }
+        let variance = get_regularized_variance(score);
+        let score0 = elo_to_score(self.elo0);
+        let score1 = elo_to_score(self.elo1);
+        f64::from(score.get_games())
+            * (score1 - score0)
+            * (2.0 * score.get_score() - score0 - score1)
+            / (2.0 * variance)
+    }
+
+    pub fn get_status(&self, score: &MatchScore) -> SprtStatus {
+        let llr = self.get_llr(score);
+        let (lower, upper) = self.get_bounds();
+        if llr <= lower {
+            SprtStatus::AcceptH0
+        } else if llr >= upper {
+            SprtStatus::AcceptH1
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+/// The variance of the points of a single game for the test. When every game had the same result
+/// the variance is 0, which would leave the ratio without a scale, so a draw is added to the
+/// results, or a win and a loss when all games were draws.
+fn get_regularized_variance(score: &MatchScore) -> f64 {
+    let variance = score.get_variance();
+    if variance > 0.0 {
+        return variance;
+    }
+    let regularized = if score.draws == score.get_games() {
+        MatchScore::new(score.wins + 1, score.losses + 1, score.draws)
+    } else {
+        MatchScore::new(score.wins, score.losses, score.draws + 1)
+    };
+    regularized.get_variance()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_score_is_no_difference() {
+        let elo = MatchScore::new(10, 10, 5).get_elo().unwrap();
+        assert!(elo.get_difference().abs() < 1e-9);
+        assert!(elo.get_error_margin() > 0.0);
+    }
+
+    #[test]
+    fn elo_matches_the_expected_score() {
+        // A 75% score is about 191 Elo.
+        let elo = MatchScore::new(30, 10, 0).get_elo().unwrap();
+        assert!((elo.get_difference() - 190.85).abs() < 0.01);
+        assert!((elo_to_score(elo.get_difference()) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn more_games_give_a_smaller_margin() {
+        let few = MatchScore::new(6, 4, 2).get_elo().unwrap();
+        let many = MatchScore::new(60, 40, 20).get_elo().unwrap();
+        assert!(many.get_error_margin() < few.get_error_margin());
+    }
+
+    #[test]
+    fn one_sided_scores_have_no_estimate() {
+        assert_eq!(MatchScore::new(5, 0, 0).get_elo(), None);
+        assert_eq!(MatchScore::new(0, 5, 0).get_elo(), None);
+        assert_eq!(MatchScore::default().get_elo(), None);
+    }
+
+    #[test]
+    fn sprt_decides_clear_results() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        let (lower, upper) = sprt.get_bounds();
+        assert!((lower + 2.944).abs() < 0.001);
+        assert!((upper - 2.944).abs() < 0.001);
+
+        assert_eq!(
+            sprt.get_status(&MatchScore::new(3, 2, 1)),
+            SprtStatus::Continue
+        );
+        assert_eq!(
+            sprt.get_status(&MatchScore::new(700, 500, 100)),
+            SprtStatus::AcceptH1
+        );
+        assert_eq!(
+            sprt.get_status(&MatchScore::new(500, 700, 100)),
+            SprtStatus::AcceptH0
+        );
+    }
+
+    #[test]
+    fn sprt_decides_one_sided_results() {
+        let sprt = Sprt::new(0.0, 10.0, 0.05, 0.05);
+        assert_eq!(
+            sprt.get_status(&MatchScore::new(10, 0, 0)),
+            SprtStatus::AcceptH1
+        );
+        assert_eq!(
+            sprt.get_status(&MatchScore::new(0, 10, 0)),
+            SprtStatus::AcceptH0
+        );
+        // A single game is not enough to decide anything.
+        assert_eq!(
+            sprt.get_status(&MatchScore::new(1, 0, 0)),
+            SprtStatus::Continue
+        );
+        assert_eq!(sprt.get_llr(&MatchScore::default()), 0.0);
+        assert!(sprt.get_llr(&MatchScore::new(0, 0, 10)) < 0.0);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod analysis;
+pub mod elo;
+pub mod matches;
+pub mod players;
+pub mod statistics;
+pub mod tournament;
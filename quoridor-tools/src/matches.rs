@@ -0,0 +1,255 @@
+//! Plays matches between two players to find out which one is stronger.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use quoridor_core::boardstate::Player;
+use quoridor_core::gamestate::{GameStatus, Gamestate, TimeControl};
+use quoridor_core::record::GameRecord;
+
+use crate::elo::{MatchScore, Sprt, SprtStatus};
+use crate::players::MatchPlayer;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchConfig {
+    /// The number of games to play, unless the SPRT decides earlier.
+    pub games: u32,
+    pub time_control: TimeControl,
+    /// A game that is not finished after this many plies is adjudicated as a draw, since two
+    /// engines can keep walking back and forth forever.
+    pub max_plies: u16,
+    pub sprt: Option<Sprt>,
+    /// Every game is appended to this file as a game record.
+    pub records: Option<PathBuf>,
+}
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        MatchConfig {
+            games: 10,
+            time_control: TimeControl::Timed {
+                seconds: 60,
+                increment: 1,
+            },
+            max_plies: 400,
+            sprt: None,
+            records: None,
+        }
+    }
+}
+
+/// Plays a single game. A player that fails to pick a legal action resigns, a game that reaches
+/// the ply limit is returned unfinished.
+pub fn play_game(
+    white: &mut dyn MatchPlayer,
+    black: &mut dyn MatchPlayer,
+    config: &MatchConfig,
+) -> GameRecord {
+    let mut gamestate = Gamestate::new(config.time_control);
+    if white.new_game().is_err() {
+        let _ = gamestate.resign(Player::White);
+    } else if black.new_game().is_err() {
+        let _ = gamestate.resign(Player::Black);
+    }
+
+    while gamestate.get_status() == GameStatus::InProgress
+        && gamestate.get_moves().len() < usize::from(config.max_plies)
+    {
+        let player = gamestate.get_boardstate().get_active_player();
+        let selected = match player {
+            Player::White => white.select_action(&gamestate),
+            Player::Black => black.select_action(&gamestate),
+        };
+        let executed = match selected {
+            Ok(Some(action)) => gamestate.execute_action(action).is_ok(),
+            Ok(None) | Err(_) => false,
+        };
+        if !executed && gamestate.get_status() == GameStatus::InProgress {
+            let _ = gamestate.resign(player);
+        }
+    }
+
+    GameRecord::from_gamestate(&gamestate, white.get_name(), black.get_name(), &today())
+}
+
+/// Plays the match between the players, the first player has white in the odd games and black
+/// in the even games. After every game `on_game` is called with the record and the score so far.
+/// Returns the score of the first player.
+pub fn run_match(
+    first: &mut dyn MatchPlayer,
+    second: &mut dyn MatchPlayer,
+    config: &MatchConfig,
+    mut on_game: impl FnMut(&GameRecord, &MatchScore),
+) -> io::Result<MatchScore> {
+    let mut records = match &config.records {
+        Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+        None => None,
+    };
+
+    let mut score = MatchScore::default();
+    for game in 0..config.games {
+        let first_player = if game.is_multiple_of(2) {
+            Player::White
+        } else {
+            Player::Black
+        };
+        let record = match first_player {
+            Player::White => play_game(first, second, config),
+            Player::Black => play_game(second, first, config),
+        };
+
+        match record.get_status() {
+            GameStatus::Finished { won_by, .. } if won_by == first_player => score.wins += 1,
+            GameStatus::Finished { .. } => score.losses += 1,
+            GameStatus::InProgress => score.draws += 1,
+        }
+        if let Some(file) = &mut records {
+            writeln!(file, "{}", record.get_record_notation())?;
+        }
+        on_game(&record, &score);
+
+        if let Some(sprt) = config.sprt {
+            if sprt.get_status(&score) != SprtStatus::Continue {
+                break;
+            }
+        }
+    }
+
+    Ok(score)
+}
+
+/// The current date in the `YYYY.MM.DD` format of the record.
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() / 86_400) as i64;
+    let (year, month, day) = civil_from_days(days);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+/// Converts days since 1970-01-01 to a date in the Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use quoridor_core::actions::Action;
+    use quoridor_engine::client::Result;
+    use quoridor_engine::search::SearchLimit;
+
+    use super::*;
+    use crate::players::AlphaBetaPlayer;
+
+    /// A player that never finds a move.
+    struct Resigner;
+
+    impl MatchPlayer for Resigner {
+        fn get_name(&self) -> &str {
+            "resigner"
+        }
+
+        fn new_game(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn select_action(&mut self, _gamestate: &Gamestate) -> Result<Option<Action>> {
+            Ok(None)
+        }
+    }
+
+    fn config(games: u32) -> MatchConfig {
+        MatchConfig {
+            games,
+            time_control: TimeControl::Unlimited,
+            ..MatchConfig::default()
+        }
+    }
+
+    #[test]
+    fn dates_are_converted() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+        assert_eq!(civil_from_days(20_012), (2024, 10, 16));
+    }
+
+    #[test]
+    fn colours_alternate_and_records_are_written() {
+        let path = std::env::temp_dir().join(format!("quoridor-match-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut engine = AlphaBetaPlayer::new("engine", Some(SearchLimit::Depth(1)));
+        let mut games = Vec::new();
+        let score = run_match(
+            &mut engine,
+            &mut Resigner,
+            &MatchConfig {
+                records: Some(path.clone()),
+                ..config(2)
+            },
+            |record, _| games.push((record.get_white().to_owned(), record.get_status())),
+        )
+        .unwrap();
+
+        assert_eq!(score, MatchScore::new(2, 0, 0));
+        assert_eq!(games[0].0, "engine");
+        assert_eq!(games[1].0, "resigner");
+
+        let records = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let records: Vec<&str> = records.split("\n\n[").collect();
+        assert_eq!(records.len(), 2);
+        assert!(GameRecord::from_record_notation(records[0]).is_ok());
+    }
+
+    #[test]
+    fn unfinished_games_are_draws() {
+        let mut white = AlphaBetaPlayer::new("white", Some(SearchLimit::Depth(1)));
+        let mut black = AlphaBetaPlayer::new("black", Some(SearchLimit::Depth(1)));
+        let record = play_game(
+            &mut white,
+            &mut black,
+            &MatchConfig {
+                max_plies: 4,
+                ..config(1)
+            },
+        );
+        assert_eq!(record.get_status(), GameStatus::InProgress);
+        assert_eq!(record.get_actions().len(), 4);
+    }
+
+    #[test]
+    fn sprt_stops_the_match() {
+        let mut engine = AlphaBetaPlayer::new("engine", Some(SearchLimit::Depth(1)));
+        let sprt = Sprt::new(0.0, 50.0, 0.05, 0.05);
+        let score = run_match(
+            &mut engine,
+            &mut Resigner,
+            &MatchConfig {
+                sprt: Some(sprt),
+                ..config(10)
+            },
+            |_, _| (),
+        )
+        .unwrap();
+        assert_eq!(sprt.get_status(&score), SprtStatus::AcceptH1);
+        assert!(score.get_games() < 10);
+    }
+}
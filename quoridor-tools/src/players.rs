@@ -0,0 +1,332 @@
+//! The players of a match, either an engine of the `quoridor-engine` crate running in the same
+//! process or an external engine executable that speaks QEI.
+
+use std::fmt;
+use std::time::Duration;
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::Player;
+use quoridor_core::gamestate::{Gamestate, TimeControl};
+use quoridor_engine::client::{ClientError, EngineClient, Result};
+use quoridor_engine::mcts::{Mcts, MctsBudget, MctsConfig};
+use quoridor_engine::protocol::{GoParameters, Position};
+use quoridor_engine::reference::search_limit;
+use quoridor_engine::search::{Engine, SearchLimit};
+
+/// The time to think about a move in a game without a clock.
+pub const UNTIMED_MOVE_TIME: Duration = Duration::from_secs(1);
+
+/// How long an engine may take to answer on top of the time it has left.
+const RESPONSE_GRACE: Duration = Duration::from_secs(5);
+
+/// A player that can take part in a match.
+pub trait MatchPlayer: Send {
+    fn get_name(&self) -> &str;
+
+    /// Called before every game.
+    fn new_game(&mut self) -> Result<()>;
+
+    /// Picks the action to play in the gamestate, where it is the turn of the player. None means
+    /// the player has no action to play, the player resigns the game just like on an error.
+    fn select_action(&mut self, gamestate: &Gamestate) -> Result<Option<Action>>;
+}
+
+/// The alpha-beta engine, searching with a fixed limit or with the clock of the game.
+pub struct AlphaBetaPlayer {
+    name: String,
+    engine: Engine,
+    limit: Option<SearchLimit>,
+}
+
+impl AlphaBetaPlayer {
+    /// Creates the player, without a limit the time is taken from the clock like the reference
+    /// engine does.
+    pub fn new(name: &str, limit: Option<SearchLimit>) -> AlphaBetaPlayer {
+        AlphaBetaPlayer {
+            name: String::from(name),
+            engine: Engine::new(),
+            limit,
+        }
+    }
+}
+
+impl MatchPlayer for AlphaBetaPlayer {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<()> {
+        self.engine.clear_table();
+        Ok(())
+    }
+
+    fn select_action(&mut self, gamestate: &Gamestate) -> Result<Option<Action>> {
+        let boardstate = gamestate.get_boardstate();
+        let limit = self.limit.unwrap_or_else(|| {
+            search_limit(&go_parameters(gamestate), boardstate.get_active_player())
+        });
+        Ok(self
+            .engine
+            .search(boardstate, limit)
+            .map(|result| result.get_best_action()))
+    }
+}
+
+/// The Monte Carlo tree search with a fixed config.
+pub struct MctsPlayer {
+    name: String,
+    config: MctsConfig,
+}
+
+impl MctsPlayer {
+    pub fn new(name: &str, config: MctsConfig) -> MctsPlayer {
+        MctsPlayer {
+            name: String::from(name),
+            config,
+        }
+    }
+}
+
+impl MatchPlayer for MctsPlayer {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn select_action(&mut self, gamestate: &Gamestate) -> Result<Option<Action>> {
+        let mut mcts = Mcts::new(self.config);
+        Ok(mcts
+            .search(gamestate.get_boardstate())
+            .map(|result| result.get_best_action()))
+    }
+}
+
+/// An engine that is driven over QEI, usually an external executable.
+pub struct ProcessPlayer {
+    name: String,
+    client: EngineClient,
+}
+
+impl ProcessPlayer {
+    /// Starts the engine executable and does the handshake. The name the engine gives itself is
+    /// used when it has one.
+    pub fn spawn(program: &str) -> Result<ProcessPlayer> {
+        ProcessPlayer::connect(program, EngineClient::spawn(program, &[])?)
+    }
+
+    /// Does the handshake with an engine that is already connected.
+    pub fn connect(fallback_name: &str, mut client: EngineClient) -> Result<ProcessPlayer> {
+        let details = client.handshake(RESPONSE_GRACE)?;
+        Ok(ProcessPlayer {
+            name: String::from(details.get_name().unwrap_or(fallback_name)),
+            client,
+        })
+    }
+}
+
+impl MatchPlayer for ProcessPlayer {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<()> {
+        self.client.new_game()?;
+        self.client.wait_until_ready(RESPONSE_GRACE)
+    }
+
+    fn select_action(&mut self, gamestate: &Gamestate) -> Result<Option<Action>> {
+        let actions = gamestate
+            .get_moves()
+            .iter()
+            .map(|executed_action| executed_action.get_action())
+            .filter(|action| !matches!(action, Action::Resigned(_)))
+            .collect();
+        self.client.set_position(&Position::from_actions(actions))?;
+
+        let player = gamestate.get_boardstate().get_active_player();
+        let timeout = gamestate
+            .get_remaining_time(player)
+            .unwrap_or(UNTIMED_MOVE_TIME)
+            + RESPONSE_GRACE;
+        let outcome = self.client.go(&go_parameters(gamestate), timeout)?;
+        Ok(outcome.get_best_action())
+    }
+}
+
+/// The parameters of `go` for the clock of the gamestate. Without a clock every move gets
+/// `UNTIMED_MOVE_TIME`.
+pub fn go_parameters(gamestate: &Gamestate) -> GoParameters {
+    match gamestate.get_time_control() {
+        TimeControl::Timed { increment, .. } => {
+            let increment = Some(Duration::from_secs(increment as u64));
+            GoParameters {
+                white_time: gamestate.get_remaining_time(Player::White),
+                black_time: gamestate.get_remaining_time(Player::Black),
+                white_increment: increment,
+                black_increment: increment,
+                ..GoParameters::default()
+            }
+        }
+        TimeControl::Correspondence | TimeControl::Unlimited => GoParameters {
+            move_time: Some(UNTIMED_MOVE_TIME),
+            ..GoParameters::default()
+        },
+    }
+}
+
+/// A player that could not be created from its spec.
+#[derive(Debug)]
+pub enum PlayerError {
+    /// The spec doesn't describe a player, like a depth that is not a number.
+    InvalidSpec(String),
+    /// The engine executable could not be started.
+    Client(ClientError),
+}
+
+impl fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerError::InvalidSpec(spec) => write!(f, "Invalid player {spec}"),
+            PlayerError::Client(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for PlayerError {}
+
+impl From<ClientError> for PlayerError {
+    fn from(error: ClientError) -> Self {
+        PlayerError::Client(error)
+    }
+}
+
+/// A player as described on the command line, before it is created.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlayerSpec {
+    AlphaBeta(Option<SearchLimit>),
+    Mcts(MctsConfig),
+    /// The path of an engine executable.
+    Executable(String),
+}
+
+/// Reads a player from the command line: `alphabeta`, `alphabeta:<depth>`, `mcts`,
+/// `mcts:<iterations>` or the path of an engine executable. Nothing is started, so this is also
+/// how specs are checked up front.
+pub fn parse_spec(spec: &str) -> std::result::Result<PlayerSpec, PlayerError> {
+    let (kind, argument) = match spec.split_once(':') {
+        Some((kind, argument)) => (kind, Some(argument)),
+        None => (spec, None),
+    };
+    let invalid = || PlayerError::InvalidSpec(String::from(spec));
+
+    match kind {
+        "alphabeta" => {
+            let limit = match argument {
+                Some(depth) => Some(SearchLimit::Depth(depth.parse().map_err(|_| invalid())?)),
+                None => None,
+            };
+            Ok(PlayerSpec::AlphaBeta(limit))
+        }
+        "mcts" => {
+            let mut config = MctsConfig::default();
+            if let Some(iterations) = argument {
+                config.budget = MctsBudget::Iterations(iterations.parse().map_err(|_| invalid())?);
+            }
+            Ok(PlayerSpec::Mcts(config))
+        }
+        _ => Ok(PlayerSpec::Executable(String::from(spec))),
+    }
+}
+
+/// Creates a player from the command line, see `parse_spec`. An engine executable is started and
+/// has to complete the handshake.
+pub fn player_from_spec(spec: &str) -> std::result::Result<Box<dyn MatchPlayer>, PlayerError> {
+    match parse_spec(spec)? {
+        PlayerSpec::AlphaBeta(limit) => Ok(Box::new(AlphaBetaPlayer::new(spec, limit))),
+        PlayerSpec::Mcts(config) => Ok(Box::new(MctsPlayer::new(spec, config))),
+        PlayerSpec::Executable(program) => Ok(Box::new(ProcessPlayer::spawn(&program)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader};
+    use std::thread;
+
+    use quoridor_engine::reference;
+
+    use super::*;
+
+    #[test]
+    fn untimed_games_use_a_move_time() {
+        let parameters = go_parameters(&Gamestate::new(TimeControl::Unlimited));
+        assert_eq!(parameters.move_time, Some(UNTIMED_MOVE_TIME));
+        assert_eq!(parameters.white_time, None);
+
+        let parameters = go_parameters(&Gamestate::new(TimeControl::Timed {
+            seconds: 60,
+            increment: 2,
+        }));
+        assert_eq!(parameters.move_time, None);
+        assert!(parameters.white_time.unwrap() <= Duration::from_secs(60));
+        assert_eq!(parameters.black_increment, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn specs_create_players() {
+        assert_eq!(
+            player_from_spec("alphabeta:2").unwrap().get_name(),
+            "alphabeta:2"
+        );
+        assert_eq!(player_from_spec("mcts").unwrap().get_name(), "mcts");
+        assert!(matches!(
+            player_from_spec("alphabeta:deep"),
+            Err(PlayerError::InvalidSpec(_))
+        ));
+        assert!(matches!(
+            player_from_spec("./no-such-quoridor-engine"),
+            Err(PlayerError::Client(_))
+        ));
+    }
+
+    #[test]
+    fn specs_are_parsed_without_starting_engines() {
+        assert_eq!(
+            parse_spec("alphabeta:3").unwrap(),
+            PlayerSpec::AlphaBeta(Some(SearchLimit::Depth(3)))
+        );
+        assert_eq!(
+            parse_spec("./no-such-quoridor-engine").unwrap(),
+            PlayerSpec::Executable(String::from("./no-such-quoridor-engine"))
+        );
+        assert!(matches!(
+            parse_spec("mcts:many"),
+            Err(PlayerError::InvalidSpec(_))
+        ));
+    }
+
+    #[test]
+    fn process_player_plays_through_qei() {
+        let (engine_input, client_output) = io::pipe().unwrap();
+        let (client_input, engine_output) = io::pipe().unwrap();
+        thread::spawn(move || reference::run(BufReader::new(engine_input), engine_output));
+        let client = EngineClient::from_streams(BufReader::new(client_input), client_output);
+
+        let mut player = ProcessPlayer::connect("reference", client).unwrap();
+        assert_eq!(player.get_name(), reference::ENGINE_NAME);
+        player.new_game().unwrap();
+
+        let mut gamestate = Gamestate::new(TimeControl::Timed {
+            seconds: 10,
+            increment: 0,
+        });
+        gamestate
+            .execute_action(Action::from_notation("E2").unwrap())
+            .unwrap();
+        let action = player.select_action(&gamestate).unwrap().unwrap();
+        assert!(gamestate.execute_action(action).is_ok());
+    }
+}
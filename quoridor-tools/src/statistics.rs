@@ -0,0 +1,214 @@
+//! A summary of how well both players played a game, built from the analysis of the game. The
+//! summary serializes to JSON, so the web interface can chart it.
+
+use std::time::Duration;
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::{Boardstate, Player};
+use quoridor_core::error::Result;
+use quoridor_core::gamestate::Gamestate;
+use serde::Serialize;
+
+use crate::analysis::{GameAnalysis, MoveLabel};
+
+/// The largest loss a single move counts for in the average, a missed forced win would otherwise
+/// outweigh all the other moves of the game. This is ten steps of the default evaluation.
+pub const MAX_COUNTED_LOSS: i32 = 1_000;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PlayerStatistics {
+    pub moves: u32,
+    /// The average evaluation the moves lost compared to the best action, in evaluation units.
+    pub average_evaluation_loss: f64,
+    pub inaccuracies: u32,
+    pub mistakes: u32,
+    pub blunders: u32,
+    pub walls_placed: u32,
+    /// The number of steps all the placed walls added to the shortest path of the opponent.
+    pub wall_steps_added: i32,
+    /// The steps added to the shortest path of the opponent per placed wall.
+    pub wall_efficiency: f64,
+    pub total_time_ms: u64,
+    pub average_move_time_ms: f64,
+    pub longest_move_time_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct GameStatistics {
+    pub white: PlayerStatistics,
+    pub black: PlayerStatistics,
+}
+
+impl GameStatistics {
+    pub fn get_player(&self, player: Player) -> &PlayerStatistics {
+        match player {
+            Player::White => &self.white,
+            Player::Black => &self.black,
+        }
+    }
+
+    fn get_player_mut(&mut self, player: Player) -> &mut PlayerStatistics {
+        match player {
+            Player::White => &mut self.white,
+            Player::Black => &mut self.black,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("The statistics serialize to JSON")
+    }
+}
+
+/// Sums up the moves of the gamestate per player. The evaluation loss and the labels come from
+/// the analysis of the same game, the time of a move comes from `ExecutedAction::get_time_spent`,
+/// which is the time charged to the clock of the player.
+pub fn game_statistics(gamestate: &Gamestate, analysis: &GameAnalysis) -> Result<GameStatistics> {
+    let mut statistics = GameStatistics::default();
+    let mut losses = [0i64; 2];
+
+    for analysis in analysis.get_moves() {
+        let player_statistics = statistics.get_player_mut(analysis.get_player());
+        match analysis.get_label() {
+            Some(MoveLabel::Inaccuracy) => player_statistics.inaccuracies += 1,
+            Some(MoveLabel::Mistake) => player_statistics.mistakes += 1,
+            Some(MoveLabel::Blunder) => player_statistics.blunders += 1,
+            None => (),
+        }
+        losses[player_index(analysis.get_player())] +=
+            i64::from(analysis.get_loss().min(MAX_COUNTED_LOSS));
+    }
+
+    let mut boardstate = Boardstate::new();
+    for executed_action in gamestate.get_moves() {
+        let action = executed_action.get_action();
+        if matches!(action, Action::Resigned(_)) {
+            continue;
+        }
+        let player = executed_action.get_player();
+        let opponent = player.get_opponent();
+
+        let distance_before = boardstate.get_distance_to_goal(opponent);
+        boardstate.apply_action(action)?;
+
+        let player_statistics = statistics.get_player_mut(player);
+        player_statistics.moves += 1;
+        add_time(player_statistics, executed_action.get_time_spent());
+        if let Action::Wall(_) = action {
+            player_statistics.walls_placed += 1;
+            if let (Some(before), Some(after)) =
+                (distance_before, boardstate.get_distance_to_goal(opponent))
+            {
+                player_statistics.wall_steps_added += i32::from(after) - i32::from(before);
+            }
+        }
+    }
+
+    for player in [Player::White, Player::Black] {
+        let player_statistics = statistics.get_player_mut(player);
+        if player_statistics.moves > 0 {
+            let moves = f64::from(player_statistics.moves);
+            player_statistics.average_evaluation_loss = losses[player_index(player)] as f64 / moves;
+            player_statistics.average_move_time_ms = player_statistics.total_time_ms as f64 / moves;
+        }
+        if player_statistics.walls_placed > 0 {
+            player_statistics.wall_efficiency = f64::from(player_statistics.wall_steps_added)
+                / f64::from(player_statistics.walls_placed);
+        }
+    }
+
+    Ok(statistics)
+}
+
+fn add_time(statistics: &mut PlayerStatistics, time: Duration) {
+    let milliseconds = time.as_millis() as u64;
+    statistics.total_time_ms += milliseconds;
+    statistics.longest_move_time_ms = statistics.longest_move_time_ms.max(milliseconds);
+}
+
+fn player_index(player: Player) -> usize {
+    match player {
+        Player::White => 0,
+        Player::Black => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use quoridor_core::gamestate::{ManualClock, TimeControl};
+    use quoridor_core::record::GameRecord;
+    use quoridor_engine::search::{Engine, SearchLimit};
+
+    use super::*;
+    use crate::analysis::{analyse_game, AnalysisConfig};
+
+    /// Plays the moves, the clock advances the number of seconds before every move.
+    fn play(moves: &[(&str, u64)]) -> Gamestate {
+        let clock = Arc::new(ManualClock::new());
+        let mut gamestate = Gamestate::with_clock(TimeControl::Unlimited, clock.clone());
+        for (notation, seconds) in moves {
+            clock.advance(Duration::from_secs(*seconds));
+            gamestate
+                .execute_action(Action::from_notation(notation).unwrap())
+                .unwrap();
+        }
+        gamestate
+    }
+
+    fn statistics(gamestate: &Gamestate) -> GameStatistics {
+        let record = GameRecord::from_gamestate(gamestate, "white", "black", "?");
+        let config = AnalysisConfig {
+            limit: SearchLimit::Depth(1),
+            ..AnalysisConfig::default()
+        };
+        let analysis = analyse_game(&record, &mut Engine::new(), &config).unwrap();
+        game_statistics(gamestate, &analysis).unwrap()
+    }
+
+    #[test]
+    fn time_is_counted_per_player() {
+        let statistics = statistics(&play(&[("E2", 3), ("E8", 5), ("E3", 1), ("E7", 2)]));
+        assert_eq!(statistics.white.moves, 2);
+        assert_eq!(statistics.white.total_time_ms, 4_000);
+        assert_eq!(statistics.white.average_move_time_ms, 2_000.0);
+        assert_eq!(statistics.white.longest_move_time_ms, 3_000);
+        assert_eq!(statistics.black.total_time_ms, 7_000);
+    }
+
+    #[test]
+    fn walls_count_the_steps_they_add() {
+        // The wall right in front of the white pawn makes it walk around, the wall on the side
+        // of the board changes nothing for black.
+        let statistics = statistics(&play(&[("E2", 0), ("D2h", 0), ("A6h", 0), ("E8", 0)]));
+        assert_eq!(statistics.white.walls_placed, 1);
+        assert_eq!(statistics.black.walls_placed, 1);
+        assert_eq!(statistics.white.wall_steps_added, 0);
+        assert_eq!(statistics.white.wall_efficiency, 0.0);
+        assert!(statistics.black.wall_steps_added > 0);
+        assert_eq!(
+            statistics.black.wall_efficiency,
+            f64::from(statistics.black.wall_steps_added)
+        );
+    }
+
+    #[test]
+    fn bad_moves_are_counted() {
+        let statistics = statistics(&play(&[("E2", 0), ("E8", 0), ("E1", 0), ("E7", 0)]));
+        assert!(statistics.white.average_evaluation_loss > 0.0);
+        assert_eq!(
+            statistics.white.inaccuracies + statistics.white.mistakes + statistics.white.blunders,
+            1
+        );
+        assert_eq!(statistics.black.average_evaluation_loss, 0.0);
+    }
+
+    #[test]
+    fn statistics_serialize_to_json() {
+        let json = statistics(&play(&[("E2", 1)])).to_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["white"]["moves"], 1);
+        assert_eq!(value["white"]["total_time_ms"], 1_000);
+        assert_eq!(value["black"]["moves"], 0);
+    }
+}
@@ -0,0 +1,551 @@
+//! Tournaments between many engines. The schedule and the results are kept in a state file, so
+//! an interrupted tournament continues with the games that were not played yet.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::thread;
+
+use quoridor_core::actions::Action;
+use quoridor_core::boardstate::Player;
+use quoridor_core::gamestate::{GameStatus, Gamestate, TimeControl};
+use quoridor_core::record::GameRecord;
+use quoridor_engine::client;
+use serde::{Deserialize, Serialize};
+
+use crate::matches::{play_game, MatchConfig};
+use crate::players::{MatchPlayer, PlayerError};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TournamentFormat {
+    /// Every engine plays every other engine.
+    RoundRobin,
+    /// The first engine plays every other engine, the others don't play each other.
+    Gauntlet,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TournamentConfig {
+    pub format: TournamentFormat,
+    /// The specs of the engines, as accepted by `parse_spec`.
+    pub engines: Vec<String>,
+    /// The number of games between every pair of engines, the colours alternate.
+    pub games_per_pairing: u32,
+    #[serde(with = "time_control_notation")]
+    pub time_control: TimeControl,
+    pub max_plies: u16,
+    /// The number of games that are played at the same time.
+    pub concurrency: usize,
+    /// Every game is appended to this file as a game record.
+    pub records: Option<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    /// The game was adjudicated after the ply limit.
+    Draw,
+}
+
+impl GameResult {
+    pub fn from_status(status: GameStatus) -> GameResult {
+        match status {
+            GameStatus::Finished {
+                won_by: Player::White,
+                ..
+            } => GameResult::WhiteWins,
+            GameStatus::Finished {
+                won_by: Player::Black,
+                ..
+            } => GameResult::BlackWins,
+            GameStatus::InProgress => GameResult::Draw,
+        }
+    }
+}
+
+/// A game of the schedule, the players are indices into the engines of the config.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledGame {
+    pub white: usize,
+    pub black: usize,
+    /// None while the game is not played yet.
+    pub result: Option<GameResult>,
+}
+
+/// A tournament with its schedule and the results so far.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tournament {
+    config: TournamentConfig,
+    games: Vec<ScheduledGame>,
+}
+
+impl Tournament {
+    /// Creates a tournament and schedules all its games.
+    pub fn new(config: TournamentConfig) -> Tournament {
+        let engines = config.engines.len();
+        let pairings: Vec<(usize, usize)> = match config.format {
+            TournamentFormat::RoundRobin => (0..engines)
+                .flat_map(|first| (first + 1..engines).map(move |second| (first, second)))
+                .collect(),
+            TournamentFormat::Gauntlet => (1..engines).map(|second| (0, second)).collect(),
+        };
+
+        // All pairings play their first game before any pairing plays its second, so a
+        // tournament that is stopped halfway still has results for every pairing.
+        let mut games = Vec::new();
+        for game in 0..config.games_per_pairing {
+            for &(first, second) in &pairings {
+                let (white, black) = if game.is_multiple_of(2) {
+                    (first, second)
+                } else {
+                    (second, first)
+                };
+                games.push(ScheduledGame {
+                    white,
+                    black,
+                    result: None,
+                });
+            }
+        }
+
+        Tournament { config, games }
+    }
+
+    /// Reads a tournament from a state file written by `save`. A schedule with a game between
+    /// engines that are not in the config, or of an engine against itself, is invalid data.
+    pub fn load(path: &Path) -> io::Result<Tournament> {
+        let state = fs::read_to_string(path)?;
+        let tournament: Tournament = serde_json::from_str(&state).map_err(io::Error::from)?;
+        let engines = tournament.config.engines.len();
+        for (index, game) in tournament.games.iter().enumerate() {
+            if game.white >= engines || game.black >= engines || game.white == game.black {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Game {} of the schedule is between engines {} and {} of {engines}",
+                        index + 1,
+                        game.white,
+                        game.black
+                    ),
+                ));
+            }
+        }
+        Ok(tournament)
+    }
+
+    /// Writes the tournament to a state file. The file is replaced in one step, so an
+    /// interruption while saving doesn't leave a broken state behind.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let state = serde_json::to_string_pretty(self).map_err(io::Error::from)?;
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, state)?;
+        fs::rename(temporary, path)
+    }
+
+    pub fn get_config(&self) -> &TournamentConfig {
+        &self.config
+    }
+
+    pub fn get_games(&self) -> &Vec<ScheduledGame> {
+        &self.games
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.games.iter().all(|game| game.result.is_some())
+    }
+
+    /// Plays the games that have no result yet on a pool of `concurrency` workers. The players
+    /// of every game are created with `create_player` from the spec of the engine, a player that
+    /// can't be created loses the game. After every game the state is saved to `state_path` and
+    /// `on_game` is called with the record.
+    pub fn run<F>(
+        &mut self,
+        state_path: Option<&Path>,
+        create_player: F,
+        mut on_game: impl FnMut(&GameRecord),
+    ) -> io::Result<()>
+    where
+        F: Fn(&str) -> Result<Box<dyn MatchPlayer>, PlayerError> + Sync,
+    {
+        let mut records = match &self.config.records {
+            Some(path) => Some(OpenOptions::new().create(true).append(true).open(path)?),
+            None => None,
+        };
+        let match_config = MatchConfig {
+            time_control: self.config.time_control,
+            max_plies: self.config.max_plies,
+            ..MatchConfig::default()
+        };
+
+        // The workers get the specs of the engines with the game, so they don't borrow the
+        // tournament while the results are written into it.
+        let pending: VecDeque<(usize, String, String)> = self
+            .games
+            .iter()
+            .enumerate()
+            .filter(|(_, game)| game.result.is_none())
+            .map(|(index, game)| {
+                let engines = &self.config.engines;
+                (
+                    index,
+                    engines[game.white].clone(),
+                    engines[game.black].clone(),
+                )
+            })
+            .collect();
+        let workers = self.config.concurrency.clamp(1, pending.len().max(1));
+        let pending = Mutex::new(pending);
+        let (sender, results) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let (pending, create_player, match_config) =
+                    (&pending, &create_player, &match_config);
+                scope.spawn(move || loop {
+                    let next = pending.lock().expect("No worker panics").pop_front();
+                    let Some((index, white, black)) = next else {
+                        break;
+                    };
+                    let record = play_scheduled_game(&white, &black, create_player, match_config);
+                    if sender.send((index, record)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            for (index, record) in results {
+                self.games[index].result = Some(GameResult::from_status(record.get_status()));
+                if let Some(file) = &mut records {
+                    writeln!(file, "{}", record.get_record_notation())?;
+                }
+                if let Some(path) = state_path {
+                    self.save(path)?;
+                }
+                on_game(&record);
+            }
+            Ok(())
+        })
+    }
+
+    /// The points every engine scored against every other engine.
+    pub fn get_crosstable(&self) -> Crosstable {
+        let engines = self.config.engines.len();
+        let mut points = vec![vec![0.0; engines]; engines];
+        let mut games = vec![vec![0; engines]; engines];
+        for game in &self.games {
+            let Some(result) = game.result else {
+                continue;
+            };
+            let (white_points, black_points) = match result {
+                GameResult::WhiteWins => (1.0, 0.0),
+                GameResult::BlackWins => (0.0, 1.0),
+                GameResult::Draw => (0.5, 0.5),
+            };
+            points[game.white][game.black] += white_points;
+            points[game.black][game.white] += black_points;
+            games[game.white][game.black] += 1;
+            games[game.black][game.white] += 1;
+        }
+
+        Crosstable {
+            engines: self.config.engines.clone(),
+            points,
+            games,
+        }
+    }
+}
+
+/// Creates the players and plays one game, when a player can't be created it loses the game.
+fn play_scheduled_game<F>(
+    white: &str,
+    black: &str,
+    create_player: &F,
+    config: &MatchConfig,
+) -> GameRecord
+where
+    F: Fn(&str) -> Result<Box<dyn MatchPlayer>, PlayerError>,
+{
+    let mut white_player = create_player(white).unwrap_or_else(|_| Box::new(Absent::new(white)));
+    let mut black_player = create_player(black).unwrap_or_else(|_| Box::new(Absent::new(black)));
+    play_game(white_player.as_mut(), black_player.as_mut(), config)
+}
+
+/// Takes the place of a player that could not be created, it resigns right away.
+struct Absent {
+    name: String,
+}
+
+impl Absent {
+    fn new(name: &str) -> Absent {
+        Absent {
+            name: String::from(name),
+        }
+    }
+}
+
+impl MatchPlayer for Absent {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> client::Result<()> {
+        Err(client::ClientError::Disconnected)
+    }
+
+    fn select_action(&mut self, _gamestate: &Gamestate) -> client::Result<Option<Action>> {
+        Err(client::ClientError::Disconnected)
+    }
+}
+
+/// The results of a tournament per pair of engines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Crosstable {
+    engines: Vec<String>,
+    points: Vec<Vec<f64>>,
+    games: Vec<Vec<u32>>,
+}
+
+impl Crosstable {
+    /// The points the engine scored against the opponent, the engines are indices into the
+    /// engines of the config.
+    pub fn get_points(&self, engine: usize, opponent: usize) -> f64 {
+        self.points[engine][opponent]
+    }
+
+    pub fn get_games(&self, engine: usize, opponent: usize) -> u32 {
+        self.games[engine][opponent]
+    }
+
+    pub fn get_total_points(&self, engine: usize) -> f64 {
+        self.points[engine].iter().sum()
+    }
+
+    /// The engines ordered by their total points, the best engine first.
+    pub fn get_standings(&self) -> Vec<usize> {
+        let mut standings: Vec<usize> = (0..self.engines.len()).collect();
+        standings.sort_by(|&first, &second| {
+            self.get_total_points(second)
+                .total_cmp(&self.get_total_points(first))
+        });
+        standings
+    }
+
+    /// Writes the crosstable as text, one row per engine ordered by the standings. A cell is
+    /// the points against the engine of the column, `-` when they played no games.
+    pub fn get_notation(&self) -> String {
+        let standings = self.get_standings();
+        let name_width = self
+            .engines
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut notation = format!("{:>3}  {:<name_width$}  {:>6}", "#", "Engine", "Points");
+        for rank in 1..=standings.len() {
+            write!(notation, "  {rank:>5}").expect("Writing to a string doesn't fail");
+        }
+        notation.push('\n');
+
+        for (rank, &engine) in standings.iter().enumerate() {
+            write!(
+                notation,
+                "{:>3}  {:<name_width$}  {:>6.1}",
+                rank + 1,
+                self.engines[engine],
+                self.get_total_points(engine)
+            )
+            .expect("Writing to a string doesn't fail");
+            for &opponent in &standings {
+                let cell = if self.get_games(engine, opponent) == 0 {
+                    String::from("-")
+                } else {
+                    format!("{:.1}", self.get_points(engine, opponent))
+                };
+                write!(notation, "  {cell:>5}").expect("Writing to a string doesn't fail");
+            }
+            notation.push('\n');
+        }
+
+        notation
+    }
+}
+
+/// Stores the time control in the state file in the same notation as the game records.
+mod time_control_notation {
+    use quoridor_core::gamestate::TimeControl;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        time_control: &TimeControl,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time_control.get_notation())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<TimeControl, D::Error> {
+        let notation = String::deserialize(deserializer)?;
+        TimeControl::from_notation(&notation).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::players::player_from_spec;
+
+    fn config(format: TournamentFormat, engines: usize) -> TournamentConfig {
+        TournamentConfig {
+            format,
+            engines: (1..=engines)
+                .map(|depth| format!("alphabeta:{depth}"))
+                .collect(),
+            games_per_pairing: 2,
+            time_control: TimeControl::Unlimited,
+            max_plies: 6,
+            concurrency: 2,
+            records: None,
+        }
+    }
+
+    fn state_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quoridor-{name}-{}.json", std::process::id()))
+    }
+
+    #[test]
+    fn round_robin_pairs_everyone() {
+        let tournament = Tournament::new(config(TournamentFormat::RoundRobin, 4));
+        assert_eq!(tournament.get_games().len(), 12);
+
+        let games = tournament.get_games();
+        assert_eq!((games[0].white, games[0].black), (0, 1));
+        assert_eq!((games[6].white, games[6].black), (1, 0));
+    }
+
+    #[test]
+    fn gauntlet_pairs_the_first_engine() {
+        let tournament = Tournament::new(config(TournamentFormat::Gauntlet, 4));
+        assert_eq!(tournament.get_games().len(), 6);
+        assert!(tournament
+            .get_games()
+            .iter()
+            .all(|game| game.white == 0 || game.black == 0));
+    }
+
+    #[test]
+    fn crosstable_counts_the_points() {
+        let mut tournament = Tournament::new(config(TournamentFormat::RoundRobin, 3));
+        let results = [
+            GameResult::WhiteWins,
+            GameResult::Draw,
+            GameResult::BlackWins,
+            GameResult::BlackWins,
+        ];
+        for (game, result) in tournament.games.iter_mut().zip(results) {
+            game.result = Some(result);
+        }
+
+        // Games: 0-1 1-0, 0-2 draw, 1-2 0-1, 1-0 0-1.
+        let crosstable = tournament.get_crosstable();
+        assert_eq!(crosstable.get_points(0, 1), 2.0);
+        assert_eq!(crosstable.get_games(0, 1), 2);
+        assert_eq!(crosstable.get_points(2, 0), 0.5);
+        assert_eq!(crosstable.get_points(2, 1), 1.0);
+        assert_eq!(crosstable.get_standings(), vec![0, 2, 1]);
+        assert!(crosstable
+            .get_notation()
+            .contains("  1  alphabeta:1     2.5      -    0.5    2.0"));
+    }
+
+    #[test]
+    fn state_is_saved_and_loaded() {
+        let path = state_path("tournament-state");
+        let mut tournament = Tournament::new(config(TournamentFormat::Gauntlet, 3));
+        tournament.games[1].result = Some(GameResult::Draw);
+        tournament.save(&path).unwrap();
+
+        let loaded = Tournament::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, tournament);
+    }
+
+    #[test]
+    fn invalid_schedule_is_not_loaded() {
+        let path = state_path("tournament-invalid-schedule");
+        for (white, black) in [(0, 3), (7, 1), (2, 2)] {
+            let mut tournament = Tournament::new(config(TournamentFormat::RoundRobin, 3));
+            tournament.games[0].white = white;
+            tournament.games[0].black = black;
+            tournament.save(&path).unwrap();
+            assert_eq!(
+                Tournament::load(&path).unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn tournament_resumes_the_unplayed_games() {
+        let path = state_path("tournament-resume");
+        let mut tournament = Tournament::new(config(TournamentFormat::RoundRobin, 3));
+        tournament.games[0].result = Some(GameResult::WhiteWins);
+        tournament.games[3].result = Some(GameResult::BlackWins);
+        tournament.save(&path).unwrap();
+
+        let mut tournament = Tournament::load(&path).unwrap();
+        let created = AtomicUsize::new(0);
+        let mut played = 0;
+        tournament
+            .run(
+                Some(&path),
+                |_| {
+                    created.fetch_add(1, Ordering::Relaxed);
+                    // The deeper engines of the config take too long for a test.
+                    player_from_spec("alphabeta:1")
+                },
+                |_| played += 1,
+            )
+            .unwrap();
+
+        assert_eq!(played, 4);
+        assert_eq!(created.load(Ordering::Relaxed), 8);
+        assert!(tournament.is_finished());
+        assert_eq!(
+            tournament.get_games()[0].result,
+            Some(GameResult::WhiteWins)
+        );
+        assert_eq!(Tournament::load(&path).unwrap(), tournament);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn missing_engines_lose() {
+        let mut tournament = Tournament::new(TournamentConfig {
+            engines: vec![
+                String::from("alphabeta:1"),
+                String::from("./no-such-quoridor-engine"),
+            ],
+            games_per_pairing: 1,
+            ..config(TournamentFormat::RoundRobin, 0)
+        });
+        tournament.run(None, player_from_spec, |_| ()).unwrap();
+        assert_eq!(
+            tournament.get_games()[0].result,
+            Some(GameResult::WhiteWins)
+        );
+    }
+}